@@ -0,0 +1,236 @@
+//backtests the link prediction scores: hide every collaboration that started after a cutoff year, score
+//pairs on what was known at the cutoff, and check the scores against who actually worked together later
+
+use std::collections::HashSet;
+use petgraph::graph::{NodeIndex, UnGraph};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::graph::CoStar;
+use crate::link_prediction::{score_pair, LinkScore};
+
+//settings for one backtest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BacktestOptions {
+    //collaborations up to and including this year are known, later ones are to be predicted
+    pub cutoff: u16,
+    //how many pairs that did not go on to work together are scored for each pair that did
+    pub negatives_per_positive: usize,
+    //score at most this many future collaborations (sampled), to bound the run time on the full graph
+    pub max_positives: usize,
+    //the cut-off for precision@k
+    pub k: usize,
+}
+
+impl Default for BacktestOptions {
+    fn default() -> Self {
+        BacktestOptions { cutoff: 2015, negatives_per_positive: 10, max_positives: 10_000, k: 100 }
+    }
+}
+
+//the graph as it was at the cutoff, and the pairs to score
+pub struct Split {
+    //same nodes as the full graph (so indices carry over), but only the edges first formed by the cutoff
+    pub train: UnGraph<String, CoStar>,
+    //pairs who had both worked before the cutoff, but first worked together after it
+    pub positives: Vec<(NodeIndex, NodeIndex)>,
+    //pairs who had both worked before the cutoff and did not work together at all
+    pub negatives: Vec<(NodeIndex, NodeIndex)>,
+}
+
+//how well one score ranks the positives above the negatives
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Evaluation {
+    pub score: LinkScore,
+    //chance that a random positive scores above a random negative (ties count half); 0.5 is guessing
+    pub auc: f64,
+    //share of positives among the k best-scored pairs
+    pub precision_at_k: f64,
+    //mean of the precision at the rank of each positive
+    pub average_precision: f64,
+}
+
+//splits a dated co-star graph at the cutoff year and samples the pairs to score
+//edges without a year are left out of both sides, since we can't tell when they formed; so are pairs whose
+//first dated title is after the cutoff but who also share an undated one, which may have come before it
+//input: the graph from build_graph_with_years, the options and a random number generator
+//output: the split; positives are sampled down to max_positives, negatives are drawn uniformly
+pub fn split_at_year(graph: &UnGraph<String, CoStar>, options: &BacktestOptions, rng: &mut impl Rng) -> Split {
+    let mut train = graph.clone();
+    train.retain_edges(|g, e| g[e].first_year.is_some_and(|year| year <= options.cutoff));
+
+    //only actors who had worked with someone by the cutoff can be scored
+    let active: Vec<NodeIndex> = train.node_indices().filter(|&n| train.neighbors(n).next().is_some()).collect();
+    let is_active: HashSet<NodeIndex> = active.iter().copied().collect();
+    let ordered = |a: NodeIndex, b: NodeIndex| if a < b { (a, b) } else { (b, a) };
+
+    let mut positives: Vec<(NodeIndex, NodeIndex)> = graph
+        .edge_indices()
+        .filter(|&e| !graph[e].undated && graph[e].first_year.is_some_and(|year| year > options.cutoff))
+        .map(|e| graph.edge_endpoints(e).unwrap())
+        .filter(|(a, b)| is_active.contains(a) && is_active.contains(b))
+        .map(|(a, b)| ordered(a, b))
+        .collect();
+    positives.sort_unstable();
+    if positives.len() > options.max_positives {
+        positives.shuffle(rng);
+        positives.truncate(options.max_positives);
+        positives.sort_unstable();
+    }
+
+    //rejection sampling; gives up after enough misses so a nearly complete graph can't loop forever
+    let wanted = positives.len() * options.negatives_per_positive;
+    let mut negatives: HashSet<(NodeIndex, NodeIndex)> = HashSet::new();
+    let mut attempts = 0;
+    while negatives.len() < wanted && attempts < 100 * wanted.max(1) && active.len() > 1 {
+        attempts += 1;
+        let (a, b) = (*active.choose(rng).unwrap(), *active.choose(rng).unwrap());
+        if a != b && graph.find_edge(a, b).is_none() {
+            negatives.insert(ordered(a, b));
+        }
+    }
+    let mut negatives: Vec<(NodeIndex, NodeIndex)> = negatives.into_iter().collect();
+    negatives.sort_unstable();
+
+    Split { train, positives, negatives }
+}
+
+//computes AUC, precision@k and average precision for one list of scored pairs
+//input: (score, whether the pair is a positive) for every pair, already shuffled so ties are broken at random,
+//and k
+fn evaluate(mut scored: Vec<(f64, bool)>, k: usize) -> (f64, f64, f64) {
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap()); //stable, so ties keep their random order
+    let positives = scored.iter().filter(|s| s.1).count();
+    let negatives = scored.len() - positives;
+
+    //AUC by counting, for each positive, the negatives below it (ties count half)
+    let mut auc = 0.0;
+    let mut i = 0;
+    let mut negatives_above = 0;
+    while i < scored.len() {
+        let j = i + scored[i..].iter().take_while(|s| s.0 == scored[i].0).count();
+        let tied_negatives = scored[i..j].iter().filter(|s| !s.1).count();
+        let tied_positives = j - i - tied_negatives;
+        let below = negatives - negatives_above - tied_negatives;
+        auc += tied_positives as f64 * (below as f64 + 0.5 * tied_negatives as f64);
+        negatives_above += tied_negatives;
+        i = j;
+    }
+    let auc = if positives == 0 || negatives == 0 { 0.5 } else { auc / (positives * negatives) as f64 };
+
+    let top = k.min(scored.len());
+    let precision_at_k = if top == 0 { 0.0 } else { scored[..top].iter().filter(|s| s.1).count() as f64 / top as f64 };
+
+    let mut hits = 0;
+    let mut precision_sum = 0.0;
+    for (rank, s) in scored.iter().enumerate() {
+        if s.1 {
+            hits += 1;
+            precision_sum += hits as f64 / (rank + 1) as f64;
+        }
+    }
+    let average_precision = if positives == 0 { 0.0 } else { precision_sum / positives as f64 };
+    (auc, precision_at_k, average_precision)
+}
+
+//backtests every link prediction score on one split
+//input: the split, k for precision@k, and a random number generator (for breaking ties)
+//output: one evaluation per score, in LinkScore::all() order
+pub fn evaluate_scores(split: &Split, k: usize, rng: &mut impl Rng) -> Vec<Evaluation> {
+    let mut pairs: Vec<((NodeIndex, NodeIndex), bool)> = split
+        .positives
+        .iter()
+        .map(|&p| (p, true))
+        .chain(split.negatives.iter().map(|&p| (p, false)))
+        .collect();
+    pairs.shuffle(rng);
+
+    LinkScore::all()
+        .into_iter()
+        .map(|score| {
+            let scored = pairs
+                .iter()
+                .map(|&((a, b), positive)| (score_pair(&split.train, a, b, score), positive))
+                .collect();
+            let (auc, precision_at_k, average_precision) = evaluate(scored, k);
+            Evaluation { score, auc, precision_at_k, average_precision }
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::graph::build_graph_with_years;
+    use crate::parser::TitleYears;
+
+    //helper function to turn a list of actor ids into a cast
+    fn cast(names: &[&str]) -> Vec<String> {
+        names.iter().map(|a| a.to_string()).collect()
+    }
+
+    //helper function to find an actor's node in a co-star graph
+    fn id<E>(graph: &UnGraph<String, E>, name: &str) -> NodeIndex {
+        graph.node_indices().find(|&n| graph[n] == name).unwrap()
+    }
+
+    //test the metrics on rankings worked out by hand
+    #[test]
+    fn test_evaluate() {
+        //perfect ranking
+        let (auc, p_at_2, ap) = evaluate(vec![(3.0, true), (2.0, true), (1.0, false)], 2);
+        assert_eq!((auc, p_at_2, ap), (1.0, 1.0, 1.0));
+        //positive, negative, positive
+        let (auc, p_at_1, ap) = evaluate(vec![(3.0, true), (2.0, false), (1.0, true)], 1);
+        assert_eq!(auc, 0.5);
+        assert_eq!(p_at_1, 1.0);
+        assert!((ap - (1.0 + 2.0 / 3.0) / 2.0).abs() < 1e-9);
+        //all tied: AUC is exactly one half
+        let (auc, _, _) = evaluate(vec![(0.0, false), (0.0, true), (0.0, false), (0.0, true)], 2);
+        assert_eq!(auc, 0.5);
+    }
+
+    //test the split: a triangle closes after the cutoff, and a newcomer's first film is not scored
+    #[test]
+    fn test_split_and_evaluate() {
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("old1".to_string(), cast(&["a", "b"]));
+        movie_to_actors.insert("old2".to_string(), cast(&["b", "c"]));
+        movie_to_actors.insert("old3".to_string(), cast(&["d", "e"]));
+        movie_to_actors.insert("new1".to_string(), cast(&["a", "c"])); //predictable: a and c share b
+        movie_to_actors.insert("new2".to_string(), cast(&["e", "newcomer"]));
+        movie_to_actors.insert("undated".to_string(), cast(&["a", "e"]));
+        movie_to_actors.insert("new3".to_string(), cast(&["a", "e"])); //may not be their first title together
+        let title_years = TitleYears::from([
+            ("old1".to_string(), 2000),
+            ("old2".to_string(), 2001),
+            ("old3".to_string(), 2002),
+            ("new1".to_string(), 2010),
+            ("new2".to_string(), 2011),
+            ("new3".to_string(), 2012),
+        ]);
+        let graph = build_graph_with_years(movie_to_actors, &title_years);
+
+        let options = BacktestOptions { cutoff: 2005, negatives_per_positive: 3, max_positives: 10, k: 1 };
+        let mut rng = StdRng::seed_from_u64(1);
+        let split = split_at_year(&graph, &options, &mut rng);
+        assert_eq!(split.train.node_count(), graph.node_count());
+        assert_eq!(split.train.edge_count(), 3);
+        let (a, c) = (id(&graph, "a"), id(&graph, "c"));
+        assert_eq!(split.positives, vec![if a < c { (a, c) } else { (c, a) }]);
+        assert_eq!(split.negatives.len(), 3);
+        for &(x, y) in &split.negatives {
+            assert!(graph.find_edge(x, y).is_none());
+            assert!(x != id(&graph, "newcomer") && y != id(&graph, "newcomer"));
+        }
+
+        let results = evaluate_scores(&split, options.k, &mut rng);
+        assert_eq!(results.len(), LinkScore::all().len());
+        let common = results.iter().find(|r| r.score == LinkScore::CommonNeighbours).unwrap();
+        assert_eq!((common.auc, common.precision_at_k, common.average_precision), (1.0, 1.0, 1.0));
+    }
+}
@@ -0,0 +1,417 @@
+//stores the data as a bipartite graph: one node per actor, one node per title, and an edge for every credit
+//this is linear in the number of credits, unlike the co-star graph where every cast becomes a clique,
+//so it is the main storage and the co-star graph is only projected (for a subset of actors) when an algorithm needs it
+//two actors who co-starred are 2 hops apart here, so every actor-to-actor hop count is halved
+
+use petgraph::graph::{NodeIndex, UnGraph};
+use petgraph::unionfind::UnionFind;
+use petgraph::visit::EdgeRef;
+use rand::seq::IteratorRandom;
+use rand::Rng;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::graph::{build_graph_with_casts, cast_billing, describe_titles, CastOptions, CoStar};
+use crate::parser::{TitleBilling, TitleYears};
+
+//set on the edge of a credit without an ordering, over its place in the cast, so it sorts after every billed
+//credit of the title but keeps its place among the unbilled ones
+pub const UNBILLED: u32 = 1 << 31;
+
+//a node of the bipartite graph is either an actor or a title, each holding its IMDb id
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Node {
+    Actor(String),
+    Title(String),
+}
+
+//the bipartite graph plus lookups from IMDb ids to nodes
+//each credit's edge holds the actor's billing on the title: IMDb's ordering (1 = top billed), or for a credit
+//without one its place in the cast with the UNBILLED bit set
+pub struct ActorTitleGraph {
+    pub graph: UnGraph<Node, u32>,
+    pub actor_index: HashMap<String, NodeIndex>,
+    pub title_index: HashMap<String, NodeIndex>,
+    //whether the billing on the edges is real; false when the input did not say how credits were billed
+    //(e.g. a cleaned tsv without an ordering column), so the edges only hold each cast's order in the file
+    pub billed: bool,
+}
+
+impl ActorTitleGraph {
+    //builds the bipartite graph, billing each cast in the order it is listed
+    //input: a map of movie IDs to lists of actor IDs in billing order (as returned by the parser)
+    //output: the graph, with one edge per (title, actor) credit (set billed to false if the casts were not
+    //actually in billing order)
+    pub fn build(movie_to_actors: &HashMap<String, Vec<String>>) -> Self {
+        Self::build_billed(movie_to_actors, &TitleBilling::new())
+    }
+
+    //same as build, but each credit's edge holds its ordering from the parser
+    pub fn build_billed(movie_to_actors: &HashMap<String, Vec<String>>, billing: &TitleBilling) -> Self {
+        let mut graph = UnGraph::<Node, u32>::new_undirected();
+        let mut actor_index: HashMap<String, NodeIndex> = HashMap::new();
+        let mut title_index: HashMap<String, NodeIndex> = HashMap::new();
+
+        //visit titles in a fixed order so node indices don't depend on hashmap order
+        let mut titles: Vec<_> = movie_to_actors.iter().collect();
+        titles.sort_unstable_by(|a, b| a.0.cmp(b.0));
+
+        for (title, actors) in titles {
+            let t = graph.add_node(Node::Title(title.clone()));
+            title_index.insert(title.clone(), t);
+            for (actor, (ordering, place)) in actors.iter().zip(cast_billing(title, actors.len(), billing)) {
+                let a = *actor_index
+                    .entry(actor.clone())
+                    .or_insert_with(|| graph.add_node(Node::Actor(actor.clone())));
+                if graph.find_edge(t, a).is_none() { //keep the best billing of an actor credited twice
+                    graph.add_edge(t, a, ordering.unwrap_or(place as u32 | UNBILLED));
+                }
+            }
+        }
+
+        ActorTitleGraph { graph, actor_index, title_index, billed: true }
+    }
+
+    pub fn actor_count(&self) -> usize {
+        self.actor_index.len()
+    }
+
+    pub fn title_count(&self) -> usize {
+        self.title_index.len()
+    }
+
+    //the IMDb id of a node (actor or title)
+    pub fn id(&self, node: NodeIndex) -> &str {
+        match &self.graph[node] {
+            Node::Actor(id) | Node::Title(id) => id,
+        }
+    }
+
+    pub fn is_actor(&self, node: NodeIndex) -> bool {
+        matches!(self.graph[node], Node::Actor(_))
+    }
+
+    //every actor node
+    pub fn actors(&self) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.graph.node_indices().filter(|&n| self.is_actor(n))
+    }
+
+    //everyone who shares at least one title with the given actor
+    pub fn co_stars(&self, actor: NodeIndex) -> HashSet<NodeIndex> {
+        self.graph
+            .neighbors(actor)
+            .flat_map(|title| self.graph.neighbors(title))
+            .filter(|&other| other != actor)
+            .collect()
+    }
+
+    //bfs over the bipartite graph, stopping early at `target` or after `max_actor_hops` actor-to-actor hops
+    //returns each reached node with its bipartite distance and the node it was reached from
+    fn bfs(
+        &self,
+        start: NodeIndex,
+        max_actor_hops: Option<usize>,
+        target: Option<NodeIndex>,
+    ) -> HashMap<NodeIndex, (usize, Option<NodeIndex>)> {
+        let max_dist = max_actor_hops.map(|h| 2 * h);
+        let mut seen = HashMap::new();
+        let mut queue = VecDeque::new();
+        seen.insert(start, (0, None));
+        queue.push_back(start);
+
+        while let Some(node) = queue.pop_front() {
+            if Some(node) == target {
+                break;
+            }
+            let dist = seen[&node].0;
+            if max_dist.is_some_and(|m| dist >= m) {
+                continue;
+            }
+            for neighbor in self.graph.neighbors(node) {
+                if let std::collections::hash_map::Entry::Vacant(e) = seen.entry(neighbor) {
+                    e.insert((dist + 1, Some(node)));
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        seen
+    }
+
+    //finds how many co-star hops separate two actors
+    //input: the id of the first actor, the id of the second actor
+    //output: the number of hops (half the bipartite distance), if a path exists
+    pub fn shortest_path_length(&self, from: &str, to: &str) -> Option<usize> {
+        let start = *self.actor_index.get(from)?;
+        let end = *self.actor_index.get(to)?;
+        self.bfs(start, None, Some(end)).get(&end).map(|&(dist, _)| dist / 2)
+    }
+
+    //finds the chain between two actors
+    //output: the nodes on the path, alternating actor, title, actor, ..., actor (if a path exists)
+    pub fn shortest_path(&self, from: &str, to: &str) -> Option<Vec<NodeIndex>> {
+        let start = *self.actor_index.get(from)?;
+        let end = *self.actor_index.get(to)?;
+        let seen = self.bfs(start, None, Some(end));
+        seen.get(&end)?;
+
+        let mut path = vec![end];
+        let mut current = end;
+        while let Some(prev) = seen[&current].1 {
+            path.push(prev);
+            current = prev;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    //renders a path from shortest_path as a sentence, e.g. "A was in tt1 and tt3 with B, who was in tt2 with C"
+    //each hop names every title the two actors share, not just the one the path went through
+    pub fn describe_path(&self, path: &[NodeIndex], actor_id_to_name: &HashMap<String, String>) -> String {
+        let name = |node: NodeIndex| {
+            let id = self.id(node);
+            actor_id_to_name.get(id).map(|n| n.as_str()).unwrap_or(id)
+        };
+
+        let mut text = String::new();
+        for (i, hop) in path.windows(3).step_by(2).enumerate() {
+            let (from, to) = (hop[0], hop[2]);
+            let titles = describe_titles(&self.shared_titles(from, to));
+            if i == 0 {
+                text.push_str(&format!("{} was in {} with {}", name(from), titles, name(to)));
+            } else {
+                text.push_str(&format!(", who was in {} with {}", titles, name(to)));
+            }
+        }
+        text
+    }
+
+    //the ids of every title two actors were both credited on, sorted
+    pub fn shared_titles(&self, a: NodeIndex, b: NodeIndex) -> Vec<String> {
+        let of_a: HashSet<NodeIndex> = self.graph.neighbors(a).collect();
+        let mut shared: Vec<String> =
+            self.graph.neighbors(b).filter(|t| of_a.contains(t)).map(|t| self.id(t).to_string()).collect();
+        shared.sort_unstable();
+        shared
+    }
+
+    //every actor within `max_depth` co-star hops of the given actor (including the actor)
+    pub fn ego_actors(&self, actor_id: &str, max_depth: usize) -> Vec<NodeIndex> {
+        let start = match self.actor_index.get(actor_id) {
+            Some(idx) => *idx,
+            None => return Vec::new(), // actor not found
+        };
+        let mut actors: Vec<NodeIndex> = self
+            .bfs(start, Some(max_depth), None)
+            .into_keys()
+            .filter(|&n| self.is_actor(n))
+            .collect();
+        actors.sort_unstable();
+        actors
+    }
+
+    //randomly picks sample_size actors, using the caller's rng so runs can be reproduced
+    pub fn sample_actors(&self, sample_size: usize, rng: &mut impl Rng) -> Vec<NodeIndex> {
+        let mut sample = self.actors().choose_multiple(rng, sample_size);
+        sample.sort_unstable();
+        sample
+    }
+
+    //groups actors into connected components (actors are connected through the titles they share)
+    //output: a map from component ID to the actor nodes belonging to that component
+    pub fn actor_components(&self) -> HashMap<usize, Vec<NodeIndex>> {
+        let mut uf = UnionFind::new(self.graph.node_count());
+        for edge in self.graph.edge_indices() {
+            let (a, b) = self.graph.edge_endpoints(edge).unwrap();
+            uf.union(a.index(), b.index());
+        }
+
+        let mut map: HashMap<usize, Vec<NodeIndex>> = HashMap::new();
+        for node in self.actors() {
+            map.entry(uf.find(node.index())).or_default().push(node);
+        }
+        map
+    }
+
+    //everyone credited on a title, in billing order
+    pub fn cast(&self, title: NodeIndex) -> Vec<NodeIndex> {
+        self.credits(title).into_iter().map(|(_, actor)| actor).collect()
+    }
+
+    //everyone credited on a title with their ordering (None for a credit without one), in billing order
+    fn credits(&self, title: NodeIndex) -> Vec<(Option<u32>, NodeIndex)> {
+        let mut cast: Vec<(u32, NodeIndex)> = self.graph.edges(title).map(|e| (*e.weight(), e.target())).collect();
+        cast.sort_unstable();
+        cast.into_iter().map(|(w, actor)| ((w & UNBILLED == 0).then_some(w), actor)).collect()
+    }
+
+    //every title with the number of actors credited on it
+    pub fn cast_sizes(&self) -> impl Iterator<Item = (&str, usize)> + '_ {
+        self.title_index.iter().map(|(title, &t)| (title.as_str(), self.graph.neighbors(t).count()))
+    }
+
+    //projects a set of actors onto the co-star graph: the induced subgraph on those actors,
+    //with an edge between two of them for every title they share
+    //input: actor nodes of this graph
+    //output: the same kind of graph build_graph returns, so every function in graph.rs can run on it
+    pub fn project_actors(&self, actors: &[NodeIndex]) -> UnGraph<String, CoStar> {
+        self.project_actors_with(actors, &CastOptions::default())
+    }
+
+    //same as project_actors, but each title's cast is handled as the cast options say; cast limits and
+    //billing go by the whole cast of the title, not just the projected part
+    pub fn project_actors_with(&self, actors: &[NodeIndex], casts: &CastOptions) -> UnGraph<String, CoStar> {
+        let wanted: HashSet<NodeIndex> = actors.iter().copied().filter(|&a| self.is_actor(a)).collect();
+        let mut movie_to_actors: HashMap<String, Vec<String>> = HashMap::new();
+        let mut billing = TitleBilling::new();
+        for &actor in &wanted {
+            for title in self.graph.neighbors(actor) {
+                let id = self.id(title);
+                if movie_to_actors.contains_key(id) {
+                    continue;
+                }
+                let (orderings, cast) =
+                    self.credits(title).into_iter().map(|(ordering, n)| (ordering, self.id(n).to_string())).unzip();
+                billing.insert(id.to_string(), orderings);
+                movie_to_actors.insert(id.to_string(), cast);
+            }
+        }
+        let wanted_ids: HashSet<&str> = wanted.iter().map(|&a| self.id(a)).collect();
+        build_graph_with_casts(movie_to_actors, &TitleYears::new(), &billing, casts, |id| wanted_ids.contains(id))
+    }
+
+    //projects the whole graph onto the co-star graph
+    //this is quadratic in cast size, so only do it when an algorithm needs every co-star edge
+    //(cast_report in graph.rs shows which titles would cost the most)
+    pub fn project(&self) -> UnGraph<String, CoStar> {
+        self.project_with(&CastOptions::default())
+    }
+
+    //projects the whole graph, handling each cast as the cast options say
+    pub fn project_with(&self, casts: &CastOptions) -> UnGraph<String, CoStar> {
+        let all: Vec<NodeIndex> = self.actors().collect();
+        self.project_actors_with(&all, casts)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{build_graph, degree_centrality, num_connected_components, BigCastPolicy, CastLimit};
+
+    //helper function to create sample data: a 4-cycle a1-a2-a3-a4 plus a separate pair
+    fn sample_movie_to_actors() -> HashMap<String, Vec<String>> {
+        let mut data = HashMap::new();
+        data.insert("m1".to_string(), vec!["a1".to_string(), "a2".to_string()]);
+        data.insert("m2".to_string(), vec!["a2".to_string(), "a3".to_string()]);
+        data.insert("m3".to_string(), vec!["a3".to_string(), "a4".to_string()]);
+        data.insert("m4".to_string(), vec!["a4".to_string(), "a1".to_string()]);
+        data.insert("m5".to_string(), vec!["b1".to_string(), "b2".to_string()]);
+        data
+    }
+
+    //test building the bipartite graph
+    #[test]
+    fn test_build() {
+        let g = ActorTitleGraph::build(&sample_movie_to_actors());
+        assert_eq!(g.actor_count(), 6);
+        assert_eq!(g.title_count(), 5);
+        assert_eq!(g.graph.edge_count(), 10); //one edge per credit
+        assert_eq!(g.co_stars(g.actor_index["a1"]).len(), 2);
+    }
+
+    //test that hop counts are halved and paths alternate actor/title
+    #[test]
+    fn test_shortest_path() {
+        let g = ActorTitleGraph::build(&sample_movie_to_actors());
+        assert_eq!(g.shortest_path_length("a1", "a3"), Some(2));
+        assert_eq!(g.shortest_path_length("a1", "a1"), Some(0));
+        assert_eq!(g.shortest_path_length("a1", "b1"), None);
+
+        let path = g.shortest_path("a1", "a2").unwrap();
+        let ids: Vec<_> = path.iter().map(|&n| g.id(n)).collect();
+        assert_eq!(ids, ["a1", "m1", "a2"]);
+        let names: HashMap<String, String> =
+            [("a1".to_string(), "Ann".to_string()), ("a2".to_string(), "Bob".to_string())].into();
+        assert_eq!(g.describe_path(&path, &names), "Ann was in m1 with Bob");
+        assert_eq!(g.shortest_path("a1", "a3").unwrap().len(), 5);
+
+        //every title the two actors share is named, not just the one the path went through
+        let mut data = sample_movie_to_actors();
+        data.insert("m6".to_string(), vec!["a2".to_string(), "a1".to_string()]);
+        let g = ActorTitleGraph::build(&data);
+        let path = g.shortest_path("a1", "a2").unwrap();
+        assert_eq!(g.shared_titles(path[0], path[2]), vec!["m1", "m6"]);
+        assert_eq!(g.describe_path(&path, &names), "Ann was in m1 and m6 with Bob");
+    }
+
+    //test ego extraction and components
+    #[test]
+    fn test_ego_and_components() {
+        let g = ActorTitleGraph::build(&sample_movie_to_actors());
+        let ego: HashSet<_> = g.ego_actors("a1", 1).iter().map(|&n| g.id(n).to_string()).collect();
+        let expected: HashSet<_> = ["a1", "a2", "a4"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(ego, expected);
+
+        let comps = g.actor_components();
+        let mut sizes: Vec<_> = comps.values().map(|c| c.len()).collect();
+        sizes.sort();
+        assert_eq!(sizes, vec![2, 4]);
+    }
+
+    //test that projecting gives the same co-star graph as build_graph
+    #[test]
+    fn test_project() {
+        let data = sample_movie_to_actors();
+        let g = ActorTitleGraph::build(&data);
+        let projected = g.project();
+        let direct = build_graph(data);
+        assert_eq!(projected.node_count(), direct.node_count());
+        assert_eq!(projected.edge_count(), direct.edge_count());
+        assert_eq!(num_connected_components(&projected), 2);
+
+        //the projection of a subset is induced: a1's ego at depth 1 keeps only a1-a2 and a1-a4
+        let ego = g.project_actors(&g.ego_actors("a1", 1));
+        assert_eq!(ego.node_count(), 3);
+        assert_eq!(ego.edge_count(), 2);
+        let degrees = degree_centrality(&ego);
+        assert_eq!(degrees.values().max(), Some(&2));
+
+        //a title is judged by its whole cast, even when only part of it is projected
+        let mut data = sample_movie_to_actors();
+        data.insert("m6".to_string(), vec!["a1".to_string(), "b1".to_string(), "b2".to_string()]);
+        let g = ActorTitleGraph::build(&data);
+        let limit = CastLimit { max_cast: 2, policy: BigCastPolicy::Drop };
+        let casts = CastOptions { limit: Some(limit), ..CastOptions::default() };
+        let pair = [g.actor_index["a1"], g.actor_index["b1"]];
+        assert_eq!(g.project_actors(&pair).edge_count(), 1);
+        assert_eq!(g.project_actors_with(&pair, &casts).edge_count(), 0);
+        assert_eq!(g.project_with(&casts).edge_count(), 5);
+        assert_eq!(g.cast_sizes().map(|(_, n)| n).max(), Some(3));
+
+        //and billing goes by the whole cast too: b1 and b2 are billed 2nd and 3rd on m6
+        let ids = |nodes: Vec<NodeIndex>| nodes.into_iter().map(|n| g.id(n).to_string()).collect::<Vec<_>>();
+        assert_eq!(ids(g.cast(g.title_index["m6"])), vec!["a1", "b1", "b2"]);
+        let top_two = CastOptions { top_billed: Some(2), ..CastOptions::default() };
+        let bs = [g.actor_index["b1"], g.actor_index["b2"]];
+        let projected = g.project_actors_with(&bs, &top_two);
+        assert_eq!(projected.edge_count(), 1); //only through m5, where they are billed 1st and 2nd
+        let weighted = CastOptions { billing_weights: true, ..CastOptions::default() };
+        let projected = g.project_actors_with(&bs, &weighted);
+        let edge = projected.edge_weights().next().unwrap();
+        assert_eq!(edge.shared_titles, 2);
+        assert!((edge.weight - (1.0 / 2f64.sqrt() + 1.0 / 6f64.sqrt())).abs() < 1e-9);
+
+        //with IMDb's ordering, the edges keep it (b2 is billed 5th), and credits without one go last
+        let mut data = sample_movie_to_actors();
+        data.insert("m6".to_string(), vec!["a1".to_string(), "b2".to_string(), "b1".to_string()]);
+        let billing = TitleBilling::from([("m6".to_string(), vec![Some(1), Some(5), None])]);
+        let g = ActorTitleGraph::build_billed(&data, &billing);
+        let m6 = g.title_index["m6"];
+        assert_eq!(g.graph[g.graph.find_edge(m6, g.actor_index["b2"]).unwrap()], 5);
+        assert_eq!(ids(g.cast(m6)), vec!["a1", "b2", "b1"]);
+        let on_m6 = [g.actor_index["a1"], g.actor_index["b1"], g.actor_index["b2"]];
+        assert_eq!(g.project_actors_with(&on_m6, &top_two).edge_count(), 1); //only b1-b2 through m5
+        let top_five = CastOptions { top_billed: Some(5), ..CastOptions::default() };
+        assert_eq!(g.project_actors_with(&on_m6, &top_five).edge_count(), 2); //and a1-b2; b1 is unbilled on m6
+    }
+}
@@ -0,0 +1,612 @@
+//parses the command line into a Command, so main.rs only has to run it
+//usage: final_project <command> [options], see USAGE below
+
+use std::fmt;
+
+use crate::backtest::BacktestOptions;
+use crate::graph::{BigCastPolicy, CastLimit, CastOptions, PivotBudget, TitleFilter};
+use crate::link_prediction::LinkScore;
+
+pub const USAGE: &str = "\
+usage: final_project <command> [options]
+
+commands:
+  path        --from ACTOR --to ACTOR          chain of co-stars linking two actors
+  ego         --actor ACTOR [--depth N]        centrality measures on an actor's neighbourhood
+  sample      [--size N] [--seed S]            centrality measures on a random sample of actors
+  centrality  [--measure M] (--actor ACTOR [--depth N] | [--size N] [--seed S] | --full)
+                                               one measure on an ego network, a sample or the whole graph
+  components                                   connected components of the whole graph
+  communities [--method M] [--resolution R] (--actor ACTOR [--depth N] | [--size N] [--seed S] | --full)
+                                               groups of actors who mostly work with each other
+  cuts        (--actor ACTOR [--depth N] | [--size N] [--seed S] | --full)
+                                               actors and collaborations that are the only link between groups
+  brokers     (--actor ACTOR [--depth N] | [--size N] [--seed S] | --full)
+                                               actors whose co-stars don't know each other (Burt's structural holes)
+  cores       (--actor ACTOR [--depth N] | [--size N] [--seed S] | --full)
+                                               k-core sizes and the actors in the innermost core
+  clustering  (--actor ACTOR [--depth N] | [--size N] [--seed S] | --full)
+                                               triangles, transitivity and average clustering
+  collaborators --actor ACTOR [--score S] [--depth N]
+                                               actors likely to work with ACTOR next, among those within N hops
+                                               (default 2)
+  backtest    --cutoff YEAR [--k K] [--negatives N] [--positives N] [--seed S]
+                                               how well each link prediction score foresaw collaborations that
+                                               started after YEAR (needs title years in the data)
+  stats                                        size of the whole graph
+  casts       [--max-cast N]                   titles with the biggest casts and how many co-star edges each
+                                               adds, before any co-star graph is built
+  find        --name NAME                      actors whose name matches (accents and typos allowed)
+  related     --actor ACTOR [--actor ACTOR ...] [--depth N]
+                                               actors most related to one or more actors (personalized PageRank
+                                               within N hops of them, default 2)
+
+options:
+  --data PATH          cleaned tsv (nconst, primaryName, tconst, optionally startYear and ordering)
+                       [default: actor_name_data.tsv]
+  --principals PATH    IMDb title.principals dump, used when the cleaned tsv is missing
+                       [default: title.principals.tsv.gz]
+  --names PATH         IMDb name.basics dump  [default: name.basics.tsv.gz]
+  --quarantine PATH    where malformed rows of the cleaned tsv are written, only created if there are any
+                       [default: quarantine.tsv]
+  --no-quarantine      skip malformed rows without writing them anywhere
+  --titles PATH        IMDb title.basics dump, joined on to give each title its type, year, runtime and genres
+                       (optional)
+  --type T,...         only build the graph from titles of these types, e.g. movie,tvMovie (needs --titles)
+  --years FROM-TO      only from titles that came out in these years, e.g. 1990-2010, 2000- or -1980
+                       (needs --titles)
+  --genre G,...        only from titles with any of these genres, e.g. Action (needs --titles)
+  --max-cast N         co-star graphs leave out titles with more than N actors (talk shows, documentaries,
+                       long-running soaps), whose casts would add up to N(N-1)/2 edges each
+  --big-casts P        drop, or down-weight so those titles count (N-1)/(cast-1) of a title  [default: drop]
+  --top-billed N       co-star graphs only connect the actors billed in the top N of each title
+  --billing-weights    a shared title counts 1/sqrt(billing of one * billing of the other), so two leads
+                       count 1 and two extras far less
+  --snapshot PATH      where the built graph is cached  [default: actor_graph.snap]
+  --top K              how many rows to print  [default: 10]
+  --measure M          degree, strength, closeness, harmonic, pagerank, eigenvector, katz,
+                       coreness, clustering, betweenness or approx-betweenness  [default: degree]
+  --damping D          chance that the PageRank walk follows an edge rather than restarting  [default: 0.85]
+  --alpha A            attenuation factor for katz, above 0 and below 1 / largest degree
+                       [default: 0.9 / largest degree]
+  --method M           louvain, or label-propagation which is much cheaper on the full graph
+                       [default: louvain]
+  --resolution R       higher finds more, smaller communities (franchises), lower fewer, larger ones
+                       (film industries)  [default: 1.0]
+  --score S            common-neighbours, jaccard, adamic-adar, resource-allocation or
+                       preferential-attachment  [default: adamic-adar]
+  --k K                precision@K for backtest  [default: 100]
+  --negatives N        pairs that never worked together scored per pair that did  [default: 10]
+  --positives N        most future collaborations backtest scores (sampled)  [default: 10000]
+  --seed S             seed for anything random (samples, pivots), so runs can be repeated
+  --pivots N           sources sampled by approx-betweenness  [default: 1000]
+  --epsilon E          instead of --pivots, sample enough sources that every approx-betweenness score is
+                       within E of the exact score with probability 1 - delta
+  --delta D            see --epsilon  [default: 0.1]
+
+ACTOR is an IMDb id such as nm1231899 or a name such as \"Priyanka Chopra Jonas\".";
+
+//the centrality measures the `centrality` command can run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Measure {
+    Degree,
+    Strength,
+    Closeness,
+    //like closeness, but sums 1/distance so unreachable actors simply count 0
+    Harmonic,
+    //weighted by shared titles, so frequent collaborators pass on more of their score
+    PageRank,
+    Eigenvector,
+    Katz,
+    //the largest k for which the actor is in the k-core
+    Coreness,
+    //local clustering coefficient
+    Clustering,
+    Betweenness,
+    //pivot-sampled betweenness with confidence intervals, fast enough for the whole graph
+    ApproxBetweenness,
+}
+
+impl Measure {
+    pub fn all() -> [Measure; 4] {
+        [Measure::Degree, Measure::Strength, Measure::Closeness, Measure::Betweenness]
+    }
+
+    fn parse(s: &str) -> Result<Measure, CliError> {
+        match s {
+            "degree" => Ok(Measure::Degree),
+            "strength" => Ok(Measure::Strength),
+            "closeness" => Ok(Measure::Closeness),
+            "harmonic" => Ok(Measure::Harmonic),
+            "pagerank" => Ok(Measure::PageRank),
+            "eigenvector" => Ok(Measure::Eigenvector),
+            "katz" => Ok(Measure::Katz),
+            "coreness" => Ok(Measure::Coreness),
+            "clustering" => Ok(Measure::Clustering),
+            "betweenness" => Ok(Measure::Betweenness),
+            "approx-betweenness" => Ok(Measure::ApproxBetweenness),
+            _ => Err(CliError(format!("unknown measure `{}`", s))),
+        }
+    }
+}
+
+impl fmt::Display for Measure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Measure::Degree => "Degree",
+            Measure::Strength => "Strength",
+            Measure::Closeness => "Closeness",
+            Measure::Harmonic => "Harmonic",
+            Measure::PageRank => "PageRank",
+            Measure::Eigenvector => "Eigenvector",
+            Measure::Katz => "Katz",
+            Measure::Coreness => "Coreness",
+            Measure::Clustering => "Clustering",
+            Measure::Betweenness => "Betweenness",
+            Measure::ApproxBetweenness => "Approximate Betweenness",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+//how the `communities` command finds communities
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommunityMethod {
+    //modularity optimisation; higher resolution gives more, smaller communities
+    Louvain { resolution: f64 },
+    //asynchronous label propagation, near-linear in the number of edges
+    LabelPropagation,
+}
+
+//which subgraph an analysis runs on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    //everyone within `depth` hops of an actor
+    Ego { actor: String, depth: usize },
+    //a random sample of actors
+    Sample { size: usize },
+    //every actor
+    Full,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Path { from: String, to: String },
+    Centrality { target: Target, measures: Vec<Measure> },
+    Components,
+    Communities { target: Target, method: CommunityMethod },
+    Cuts { target: Target },
+    Brokers { target: Target },
+    Cores { target: Target },
+    Clustering { target: Target },
+    Collaborators { actor: String, score: LinkScore, depth: usize },
+    Backtest { options: BacktestOptions },
+    Stats,
+    Casts,
+    Find { name: String },
+    //personalized pagerank seeded from the actors, over everyone within `depth` hops of them
+    Related { actors: Vec<String>, depth: usize },
+    Help,
+}
+
+//options that apply to every command
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub data: String,
+    pub principals: String,
+    pub names: String,
+    //where skipped rows of the cleaned tsv are written; None skips them without a record
+    pub quarantine: Option<String>,
+    //title.basics, only read when given
+    pub titles: Option<String>,
+    //which titles the graph is built from
+    pub filter: TitleFilter,
+    //how co-star projections handle each cast: big casts, top billed only, billing weights
+    pub casts: CastOptions,
+    pub snapshot: String,
+    pub top: usize,
+    //seeds every random choice when given, so runs can be repeated
+    pub seed: Option<u64>,
+    pub pivots: PivotBudget,
+    pub damping: f64,
+    //katz attenuation factor; picked from the graph when not given
+    pub alpha: Option<f64>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            data: "actor_name_data.tsv".to_string(),
+            principals: "title.principals.tsv.gz".to_string(),
+            names: "name.basics.tsv.gz".to_string(),
+            quarantine: Some("quarantine.tsv".to_string()),
+            titles: None,
+            filter: TitleFilter::default(),
+            casts: CastOptions::default(),
+            snapshot: "actor_graph.snap".to_string(),
+            top: 10,
+            seed: None,
+            pivots: PivotBudget::Sources(1000),
+            damping: 0.85,
+            alpha: None,
+        }
+    }
+}
+
+//a problem with the command line, shown to the user together with USAGE
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliError(pub String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+fn parse_list(value: &str) -> Vec<String> {
+    value.split(',').map(|v| v.trim()).filter(|v| !v.is_empty()).map(|v| v.to_string()).collect()
+}
+
+//parses FROM-TO, FROM-, -TO or a single year
+fn parse_years(value: &str) -> Result<(Option<u16>, Option<u16>), CliError> {
+    let year = |s: &str| if s.is_empty() { Ok(None) } else { parse_number("--years", s).map(Some) };
+    let (from, to) = match value.split_once('-') {
+        Some((from, to)) => (year(from)?, year(to)?),
+        None => (year(value)?, year(value)?),
+    };
+    match (from, to) {
+        (None, None) => Err(CliError("`--years` needs at least one year".to_string())),
+        (Some(f), Some(t)) if f > t => Err(CliError(format!("`--years` range {} is backwards", value))),
+        range => Ok(range),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, CliError> {
+    value
+        .parse()
+        .map_err(|_| CliError(format!("`{}` expects a number, got `{}`", flag, value)))
+}
+
+//parses the arguments after the program name
+//input: e.g. ["ego", "--actor", "nm1231899", "--depth", "2"]
+//output: the command to run and the options for it
+pub fn parse_args(args: &[String]) -> Result<(Command, Options), CliError> {
+    let mut options = Options::default();
+    let command = match args.first() {
+        Some(c) => c.as_str(),
+        None => return Ok((Command::Help, options)),
+    };
+
+    //flags, in any order, each followed by its value (--actor may be repeated)
+    let mut actors = Vec::new();
+    let mut from = None;
+    let mut to = None;
+    let mut depth = None;
+    let mut size = 500;
+    let mut full = false;
+    let mut epsilon = None;
+    let mut delta = 0.1;
+    let mut measure = None;
+    let mut name = None;
+    let mut resolution: f64 = 1.0;
+    let mut method = None;
+    let mut score = LinkScore::AdamicAdar;
+    let mut backtest = BacktestOptions::default();
+    let mut cutoff = None;
+    let mut max_cast = None;
+    let mut policy = None;
+    let mut rest = args[1..].iter();
+    while let Some(flag) = rest.next() {
+        if flag == "--help" || flag == "-h" {
+            return Ok((Command::Help, options));
+        }
+        //the flags without a value
+        if flag == "--full" {
+            full = true;
+            continue;
+        }
+        if flag == "--billing-weights" {
+            options.casts.billing_weights = true;
+            continue;
+        }
+        if flag == "--no-quarantine" {
+            options.quarantine = None;
+            continue;
+        }
+        let value = rest
+            .next()
+            .ok_or_else(|| CliError(format!("`{}` needs a value", flag)))?;
+        match flag.as_str() {
+            "--data" => options.data = value.clone(),
+            "--principals" => options.principals = value.clone(),
+            "--names" => options.names = value.clone(),
+            "--quarantine" => options.quarantine = Some(value.clone()),
+            "--titles" => options.titles = Some(value.clone()),
+            "--type" => options.filter.title_types = parse_list(value),
+            "--years" => (options.filter.from_year, options.filter.to_year) = parse_years(value)?,
+            "--genre" => options.filter.genres = parse_list(value),
+            "--top-billed" => options.casts.top_billed = Some(parse_number(flag, value)?),
+            "--max-cast" => max_cast = Some(parse_number(flag, value)?),
+            "--big-casts" => {
+                policy = match value.as_str() {
+                    "drop" => Some(BigCastPolicy::Drop),
+                    "down-weight" => Some(BigCastPolicy::DownWeight),
+                    _ => return Err(CliError(format!("unknown big-cast policy `{}`", value))),
+                }
+            }
+            "--snapshot" => options.snapshot = value.clone(),
+            "--top" => options.top = parse_number(flag, value)?,
+            "--actor" => actors.push(value.clone()),
+            "--from" => from = Some(value.clone()),
+            "--to" => to = Some(value.clone()),
+            "--depth" => depth = Some(parse_number(flag, value)?),
+            "--size" => size = parse_number(flag, value)?,
+            "--seed" => options.seed = Some(parse_number(flag, value)?),
+            "--pivots" => options.pivots = PivotBudget::Sources(parse_number(flag, value)?),
+            "--epsilon" => epsilon = Some(parse_number(flag, value)?),
+            "--delta" => delta = parse_number(flag, value)?,
+            "--alpha" => options.alpha = Some(parse_number(flag, value)?),
+            "--damping" => options.damping = parse_number(flag, value)?,
+            "--measure" => measure = Some(Measure::parse(value)?),
+            "--name" => name = Some(value.clone()),
+            "--score" => {
+                score = LinkScore::parse(value).ok_or_else(|| CliError(format!("unknown score `{}`", value)))?
+            }
+            "--cutoff" => cutoff = Some(parse_number(flag, value)?),
+            "--k" => backtest.k = parse_number(flag, value)?,
+            "--negatives" => backtest.negatives_per_positive = parse_number(flag, value)?,
+            "--positives" => backtest.max_positives = parse_number(flag, value)?,
+            "--method" => method = Some(value.clone()),
+            "--resolution" => resolution = parse_number(flag, value)?,
+            _ => return Err(CliError(format!("unknown option `{}`", flag))),
+        }
+    }
+
+    if let Some(epsilon) = epsilon {
+        if !(epsilon > 0.0 && delta > 0.0 && delta < 1.0) {
+            return Err(CliError("`--epsilon` must be positive and `--delta` between 0 and 1".to_string()));
+        }
+        options.pivots = PivotBudget::ErrorBound { epsilon, delta };
+    }
+
+    if !(options.damping >= 0.0 && options.damping < 1.0) {
+        return Err(CliError("`--damping` must be at least 0 and below 1".to_string()));
+    }
+
+    //commands about a single actor use the last --actor given
+    let actor = actors.last().cloned();
+    if !options.filter.is_empty() && options.titles.is_none() {
+        return Err(CliError("`--type`, `--years` and `--genre` need `--titles`".to_string()));
+    }
+    if options.casts.top_billed == Some(0) {
+        return Err(CliError("`--top-billed` must be at least 1".to_string()));
+    }
+    options.casts.limit = match (max_cast, policy) {
+        (Some(0 | 1), _) => return Err(CliError("`--max-cast` must be at least 2".to_string())),
+        (Some(max_cast), policy) => Some(CastLimit { max_cast, policy: policy.unwrap_or(BigCastPolicy::Drop) }),
+        (None, Some(_)) => return Err(CliError("`--big-casts` needs `--max-cast`".to_string())),
+        (None, None) => None,
+    };
+    if options.alpha.is_some_and(|alpha| !(alpha.is_finite() && alpha > 0.0)) {
+        return Err(CliError("`--alpha` must be positive".to_string()));
+    }
+    if resolution.is_nan() || resolution <= 0.0 {
+        return Err(CliError("`--resolution` must be positive".to_string()));
+    }
+    //the subgraph for commands that run on an ego network, a sample or the whole graph
+    let target = match &actor {
+        _ if full => Target::Full,
+        Some(actor) => Target::Ego { actor: actor.clone(), depth: depth.unwrap_or(1) },
+        None => Target::Sample { size },
+    };
+
+    let require = |value: Option<String>, flag: &str| {
+        value.ok_or_else(|| CliError(format!("`{}` needs `{}`", command, flag)))
+    };
+    let command = match command {
+        "path" => Command::Path { from: require(from, "--from")?, to: require(to, "--to")? },
+        "ego" => Command::Centrality {
+            target: Target::Ego { actor: require(actor, "--actor")?, depth: depth.unwrap_or(1) },
+            measures: Measure::all().to_vec(),
+        },
+        "sample" => Command::Centrality {
+            target: Target::Sample { size },
+            measures: Measure::all().to_vec(),
+        },
+        "centrality" => Command::Centrality { target, measures: vec![measure.unwrap_or(Measure::Degree)] },
+        "components" => Command::Components,
+        "communities" => {
+            let method = match method.as_deref() {
+                None | Some("louvain") => CommunityMethod::Louvain { resolution },
+                Some("label-propagation") => CommunityMethod::LabelPropagation,
+                Some(other) => return Err(CliError(format!("unknown method `{}`", other))),
+            };
+            Command::Communities { target, method }
+        }
+        "cuts" => Command::Cuts { target },
+        "brokers" => Command::Brokers { target },
+        "cores" => Command::Cores { target },
+        "clustering" => Command::Clustering { target },
+        "collaborators" => Command::Collaborators {
+            actor: require(actor, "--actor")?,
+            score,
+            depth: depth.unwrap_or(2),
+        },
+        "backtest" => {
+            backtest.cutoff = cutoff.ok_or_else(|| CliError("`backtest` needs `--cutoff`".to_string()))?;
+            Command::Backtest { options: backtest }
+        }
+        "stats" => Command::Stats,
+        "casts" => Command::Casts,
+        "find" => Command::Find { name: require(name, "--name")? },
+        "related" => {
+            require(actor, "--actor")?;
+            Command::Related { actors, depth: depth.unwrap_or(2) }
+        }
+        "help" | "--help" | "-h" => Command::Help,
+        other => return Err(CliError(format!("unknown command `{}`", other))),
+    };
+    Ok((command, options))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|a| a.to_string()).collect()
+    }
+
+    //test parsing the commands about one or two actors, and those without options
+    #[test]
+    fn test_parse_actor_commands() {
+        let (cmd, opts) = parse_args(&args("path --from nm0000821 --to nm3154303 --top 3")).unwrap();
+        assert_eq!(cmd, Command::Path { from: "nm0000821".into(), to: "nm3154303".into() });
+        assert_eq!(opts.top, 3);
+
+        let (cmd, _) = parse_args(&args("ego --actor nm1231899 --depth 2")).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Centrality {
+                target: Target::Ego { actor: "nm1231899".into(), depth: 2 },
+                measures: Measure::all().to_vec()
+            }
+        );
+
+        assert_eq!(parse_args(&[]).unwrap().0, Command::Help);
+        assert_eq!(parse_args(&args("stats")).unwrap().0, Command::Stats);
+        let find = vec!["find".to_string(), "--name".to_string(), "Timothee Chalamet".to_string()];
+        assert_eq!(parse_args(&find).unwrap().0, Command::Find { name: "Timothee Chalamet".into() });
+    }
+
+    //test parsing the centrality command with its measures and their options
+    #[test]
+    fn test_parse_centrality() {
+        let (cmd, opts) = parse_args(&args("centrality --measure betweenness --seed 7 --data x.tsv")).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Centrality {
+                target: Target::Sample { size: 500 },
+                measures: vec![Measure::Betweenness]
+            }
+        );
+        assert_eq!(opts.data, "x.tsv");
+        assert_eq!(opts.seed, Some(7));
+
+        let (cmd, opts) =
+            parse_args(&args("centrality --full --measure approx-betweenness --epsilon 0.01")).unwrap();
+        assert_eq!(cmd, Command::Centrality { target: Target::Full, measures: vec![Measure::ApproxBetweenness] });
+        assert_eq!(opts.pivots, PivotBudget::ErrorBound { epsilon: 0.01, delta: 0.1 });
+
+        let (cmd, opts) = parse_args(&args("centrality --full --measure katz --alpha 0.05")).unwrap();
+        assert_eq!(cmd, Command::Centrality { target: Target::Full, measures: vec![Measure::Katz] });
+        assert_eq!(opts.alpha, Some(0.05));
+
+        let (cmd, opts) = parse_args(&args("related --actor nm1231899 --actor nm0000821 --damping 0.5")).unwrap();
+        assert_eq!(cmd, Command::Related { actors: vec!["nm1231899".into(), "nm0000821".into()], depth: 2 });
+        assert_eq!(opts.damping, 0.5);
+    }
+
+    //test parsing the commands that take a target: communities, cuts, brokers and cores
+    #[test]
+    fn test_parse_structure() {
+        let (cmd, _) = parse_args(&args("communities --full --resolution 2.5")).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Communities { target: Target::Full, method: CommunityMethod::Louvain { resolution: 2.5 } }
+        );
+        let (cmd, _) = parse_args(&args("communities --method label-propagation --seed 3")).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Communities { target: Target::Sample { size: 500 }, method: CommunityMethod::LabelPropagation }
+        );
+        assert_eq!(parse_args(&args("cuts --full")).unwrap().0, Command::Cuts { target: Target::Full });
+        assert_eq!(parse_args(&args("cores --full")).unwrap().0, Command::Cores { target: Target::Full });
+        assert_eq!(
+            parse_args(&args("brokers --actor nm1 --depth 2")).unwrap().0,
+            Command::Brokers { target: Target::Ego { actor: "nm1".into(), depth: 2 } }
+        );
+    }
+
+    //test parsing the link prediction commands
+    #[test]
+    fn test_parse_link_prediction() {
+        assert_eq!(
+            parse_args(&args("collaborators --actor nm1 --score jaccard")).unwrap().0,
+            Command::Collaborators { actor: "nm1".into(), score: LinkScore::Jaccard, depth: 2 }
+        );
+
+        let (cmd, opts) = parse_args(&args("backtest --cutoff 2010 --k 5 --negatives 3 --titles b.tsv.gz")).unwrap();
+        let expected = BacktestOptions { cutoff: 2010, k: 5, negatives_per_positive: 3, ..BacktestOptions::default() };
+        assert_eq!(cmd, Command::Backtest { options: expected });
+        assert_eq!(opts.titles.as_deref(), Some("b.tsv.gz"));
+    }
+
+    //test parsing the title filter
+    #[test]
+    fn test_parse_title_filter() {
+        let (_, opts) = parse_args(&args("stats --titles b.tsv --type movie,tvMovie --years 1990-2010 --genre Action")).unwrap();
+        let expected = TitleFilter {
+            title_types: vec!["movie".into(), "tvMovie".into()],
+            from_year: Some(1990),
+            to_year: Some(2010),
+            genres: vec!["Action".into()],
+        };
+        assert_eq!(opts.filter, expected);
+        assert_eq!(parse_args(&args("stats --titles b.tsv --years 2000-")).unwrap().1.filter.to_year, None);
+        assert_eq!(parse_args(&args("stats --titles b.tsv --years 1975")).unwrap().1.filter.to_year, Some(1975));
+    }
+
+    //test parsing the cast options: the cast limit, top billing and billing weights
+    #[test]
+    fn test_parse_cast_options() {
+        let (cmd, opts) = parse_args(&args("casts --max-cast 50")).unwrap();
+        assert_eq!(cmd, Command::Casts);
+        assert_eq!(opts.casts.limit, Some(CastLimit { max_cast: 50, policy: BigCastPolicy::Drop }));
+        let (_, opts) = parse_args(&args("cores --full --max-cast 50 --big-casts down-weight")).unwrap();
+        assert_eq!(opts.casts.limit, Some(CastLimit { max_cast: 50, policy: BigCastPolicy::DownWeight }));
+        assert_eq!(parse_args(&args("cores --full")).unwrap().1.casts, CastOptions::default());
+        let (cmd, opts) = parse_args(&args("cores --billing-weights --full --top-billed 5")).unwrap();
+        assert_eq!(cmd, Command::Cores { target: Target::Full });
+        assert_eq!(opts.casts, CastOptions { limit: None, top_billed: Some(5), billing_weights: true });
+    }
+
+    //test choosing where malformed rows are quarantined, or turning it off
+    #[test]
+    fn test_parse_quarantine() {
+        assert_eq!(parse_args(&args("stats")).unwrap().1.quarantine.as_deref(), Some("quarantine.tsv"));
+        let (_, opts) = parse_args(&args("stats --quarantine bad_rows.tsv")).unwrap();
+        assert_eq!(opts.quarantine.as_deref(), Some("bad_rows.tsv"));
+        assert_eq!(parse_args(&args("stats --no-quarantine")).unwrap().1.quarantine, None);
+    }
+
+    //test that bad command lines are rejected with a message
+    #[test]
+    fn test_parse_args_errors() {
+        assert!(parse_args(&args("path --from nm1")).is_err()); //missing --to
+        assert!(parse_args(&args("ego --depth two --actor nm1")).is_err());
+        assert!(parse_args(&args("stats --top")).is_err());
+        assert!(parse_args(&args("centrality --measure fame")).is_err());
+        assert!(parse_args(&args("centrality --measure harmonic")).is_ok());
+        assert!(parse_args(&args("related --depth 2")).is_err()); //no seed actor
+        assert!(parse_args(&args("communities --resolution 0")).is_err());
+        assert!(parse_args(&args("communities --method kmeans")).is_err());
+        assert!(parse_args(&args("collaborators --actor nm1 --score luck")).is_err());
+        assert!(parse_args(&args("backtest --k 5")).is_err()); //no cutoff
+        assert!(parse_args(&args("stats --type movie")).is_err()); //no --titles
+        assert!(parse_args(&args("stats --titles b.tsv --years 2010-1990")).is_err());
+        assert!(parse_args(&args("stats --titles b.tsv --years -")).is_err());
+        assert!(parse_args(&args("related --actor nm1 --damping 1")).is_err());
+        assert!(parse_args(&args("frobnicate")).is_err());
+        assert!(parse_args(&args("cores --big-casts drop")).is_err()); //no --max-cast
+        assert!(parse_args(&args("cores --max-cast 50 --big-casts shrink")).is_err());
+        assert!(parse_args(&args("cores --max-cast 1")).is_err());
+        assert!(parse_args(&args("cores --top-billed 0")).is_err());
+        assert!(parse_args(&args("centrality --full --epsilon 0.1 --delta 2")).is_err());
+        assert!(parse_args(&args("centrality --measure katz --alpha nan")).is_err());
+        assert!(parse_args(&args("centrality --measure katz --alpha -0.1")).is_err());
+    }
+}
@@ -1,171 +1,1582 @@
 //define functions to build the graph, compute centrality, and find the shortest path between nodes
 //(nodes are actors, edges are titles. a title is either a movie or series)
 
-use petgraph::graph::{UnGraph, NodeIndex};
-use petgraph::visit::{Bfs, IntoNodeReferences};
+use petgraph::graph::{EdgeIndex, UnGraph, NodeIndex};
+use petgraph::visit::{EdgeRef, IntoNodeReferences};
 use petgraph::algo::{dijkstra, connected_components};
 use std::collections::{HashMap, HashSet, VecDeque};
 use petgraph::unionfind::UnionFind;
-use rand::seq::IteratorRandom;
+use rand::seq::{IteratorRandom, SliceRandom};
 use rand::Rng;
 
+use crate::parser::{title_years, MovieToActors, TitleBilling, TitleInfo, TitleTable, TitleYears};
+
+//the payload of an edge: how many titles the two actors appeared in together, and which ones
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoStar {
+    pub shared_titles: usize,
+    pub titles: Vec<String>,
+    //the weight of the collaboration: 1 per shared title, less for titles down-weighted by a CastLimit
+    pub weight: f64,
+    //the year of their earliest shared title with a known year (None if no year is known)
+    pub first_year: Option<u16>,
+    //whether any shared title has no known year, so they may have first worked together before first_year
+    pub undated: bool,
+}
+
+//edge payloads that carry a collaboration weight, so the weighted measures can run on any graph
+//graphs without weights (unit edges) count every tie as 1
+pub trait Weighted {
+    fn weight(&self) -> f64;
+}
+
+impl Weighted for CoStar {
+    //the weight of the collaboration is the number of shared titles (minus any down-weighting of big casts)
+    fn weight(&self) -> f64 {
+        self.weight
+    }
+}
+
+impl Weighted for () {
+    fn weight(&self) -> f64 {
+        1.0
+    }
+}
+
 // constructs an undirected graph where each actor is a node, and an edge exists between actors who co-starred in the same movie
 // it takes `movie_to_actors` which is a map of movie IDs to lists of actor IDs
-// and returns `UnGraph<String, ()>`, a graph of actor connections
-pub fn build_graph(movie_to_actors: HashMap<String, Vec<String>>) -> UnGraph<String, ()> {
-    let mut graph = UnGraph::<String, ()>::new_undirected();
+// and returns `UnGraph<String, CoStar>`, a graph of actor connections where each edge remembers the shared titles
+pub fn build_graph(movie_to_actors: HashMap<String, Vec<String>>) -> UnGraph<String, CoStar> {
+    build_graph_with_years(movie_to_actors, &TitleYears::new())
+}
+
+//same as build_graph, but each edge also remembers the year the two actors first worked together
+//input: the movie to actors map, and the year of each title (titles without one don't date the edge)
+//output: the co-star graph, with first_year filled in on the edges
+pub fn build_graph_with_years(
+    movie_to_actors: HashMap<String, Vec<String>>,
+    title_years: &TitleYears,
+) -> UnGraph<String, CoStar> {
+    build_graph_with_casts(movie_to_actors, title_years, &TitleBilling::new(), &CastOptions::default(), |_| true)
+}
+
+//what happens to a title whose cast is over the limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BigCastPolicy {
+    //leave the title out of the co-star graph
+    Drop,
+    //keep its edges, but each counts (max_cast - 1) / (cast - 1) of a title, so that nobody gains more than
+    //max_cast - 1 in strength from one title
+    DownWeight,
+}
+
+//a guard against huge ensemble casts (talk shows, documentaries, long soap runs): a title with n actors adds
+//n(n-1)/2 edges, so a few of them can outweigh thousands of ordinary films in every ranking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CastLimit {
+    pub max_cast: usize,
+    pub policy: BigCastPolicy,
+}
+
+impl CastLimit {
+    //how much each edge from a title with this many actors counts (None when the title is dropped)
+    pub fn title_weight(&self, cast: usize) -> Option<f64> {
+        if cast <= self.max_cast {
+            return Some(1.0);
+        }
+        match self.policy {
+            BigCastPolicy::Drop => None,
+            BigCastPolicy::DownWeight => Some(self.max_cast.saturating_sub(1) as f64 / (cast - 1) as f64),
+        }
+    }
+}
+
+//the number of co-star edges a cast of this size adds
+pub fn clique_edges(cast: usize) -> usize {
+    cast * cast.saturating_sub(1) / 2
+}
+
+//how co-star graphs treat each title's cast; the default connects everyone on a title with weight 1
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CastOptions {
+    //drop or down-weight titles with too many actors
+    pub limit: Option<CastLimit>,
+    //only connect the actors billed in the top N of a title (the others still get a node)
+    pub top_billed: Option<usize>,
+    //weight each shared title by how high the two actors were billed on it (see billing_weight)
+    pub billing_weights: bool,
+}
+
+//how much a shared title counts for the actors billed `a`th and `b`th on it (1 = top billed):
+//1 / sqrt(a * b), so two leads count 1, a lead and the 4th billed 0.5, and extras billed 20th and 30th 0.04
+pub fn billing_weight(a: usize, b: usize) -> f64 {
+    1.0 / ((a * b) as f64).sqrt()
+}
+
+//the billing of each credit of a title's cast, in cast order: IMDb's ordering (None for a credit without one),
+//and the place used for billing weights, which is the ordering, or one after the credit before it for a
+//credit without one
+//input: the title, how many actors are in its cast, and the billing from the parser (a title missing from it
+//is billed in cast order)
+pub fn cast_billing(title: &str, cast: usize, billing: &TitleBilling) -> Vec<(Option<u32>, usize)> {
+    let mut place = 0;
+    (0..cast)
+        .map(|i| {
+            let ordering = match billing.get(title) {
+                Some(orderings) => orderings.get(i).copied().flatten(),
+                None => Some(i as u32 + 1),
+            };
+            place = ordering.map_or(place + 1, |o| o as usize);
+            (ordering, place)
+        })
+        .collect()
+}
+
+//same as build_graph_with_years, but each cast is handled as the cast options say: titles over the cast limit
+//are dropped or down-weighted, only the top billed actors are connected, and edges can be weighted by billing
+//input: the movie to actors map with each cast in billing order (as the parser returns it), the year of each
+//title, the ordering of each credit, the cast options, and which actors to put in the graph (the others still
+//count towards a title's cast size and billing, e.g. when projecting a subset of actors)
+//output: the co-star graph of the kept actors, with weights on the edges
+pub fn build_graph_with_casts(
+    movie_to_actors: HashMap<String, Vec<String>>,
+    title_years: &TitleYears,
+    billing: &TitleBilling,
+    casts: &CastOptions,
+    keep: impl Fn(&str) -> bool,
+) -> UnGraph<String, CoStar> {
+    let mut graph = UnGraph::<String, CoStar>::new_undirected();
     let mut actor_map: HashMap<String, NodeIndex> = HashMap::new();
 
-    for actors in movie_to_actors.values() {
-        for actor in actors {
-            actor_map.entry(actor.clone()).or_insert_with(|| graph.add_node(actor.clone()));
+    //visit titles in a fixed order so the title lists on edges don't depend on hashmap order
+    let mut titles: Vec<_> = movie_to_actors.iter().collect();
+    titles.sort_unstable_by(|a, b| a.0.cmp(b.0));
+
+    for (title, actors) in titles {
+        //each kept actor with their ordering and place
+        let mut cast: Vec<(Option<u32>, usize, NodeIndex)> = Vec::new();
+        for (actor, (ordering, place)) in actors.iter().zip(cast_billing(title, actors.len(), billing)) {
+            if keep(actor) {
+                let node = *actor_map.entry(actor.clone()).or_insert_with(|| graph.add_node(actor.clone()));
+                cast.push((ordering, place, node));
+            }
         }
+        let title_weight = match casts.limit {
+            Some(limit) => match limit.title_weight(actors.len()) {
+                Some(weight) => weight,
+                None => continue,
+            },
+            None => 1.0,
+        };
+        //credits without an ordering are never in the top billed; they come last, so the connected actors are
+        //a prefix of the cast
+        let connected = casts.top_billed.map_or(cast.len(), |n| {
+            cast.partition_point(|&(ordering, _, _)| ordering.is_some_and(|o| o as usize <= n))
+        });
+        let year = title_years.get(title).copied();
 
         //connect pairs
-        for i in 0..actors.len() {
-            for j in i + 1..actors.len() {
-                let a_idx = actor_map[&actors[i]];
-                let b_idx = actor_map[&actors[j]];
-                graph.update_edge(a_idx, b_idx, ());
+        for i in 0..connected {
+            for j in i + 1..connected {
+                let ((_, place_a, a_idx), (_, place_b, b_idx)) = (cast[i], cast[j]);
+                if a_idx == b_idx {
+                    continue;
+                }
+                let weight = if casts.billing_weights {
+                    title_weight * billing_weight(place_a, place_b)
+                } else {
+                    title_weight
+                };
+                match graph.find_edge(a_idx, b_idx) {
+                    Some(edge) => {
+                        let costar = &mut graph[edge];
+                        costar.shared_titles += 1;
+                        costar.weight += weight;
+                        costar.undated |= year.is_none();
+                        costar.titles.push(title.clone());
+                        costar.first_year = match (costar.first_year, year) {
+                            (Some(a), Some(b)) => Some(a.min(b)),
+                            (a, b) => a.or(b),
+                        };
+                    }
+                    None => {
+                        let costar = CoStar {
+                            shared_titles: 1,
+                            titles: vec![title.clone()],
+                            weight,
+                            first_year: year,
+                            undated: year.is_none(),
+                        };
+                        graph.add_edge(a_idx, b_idx, costar);
+                    }
+                }
             }
         }
     }
     graph
 }
 
+//which titles to build a graph from, using the title table joined from title.basics
+//an empty list or None means no restriction; e.g. only movies and TV movies from 1990 to 2010 with "Action"
+//among their genres
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TitleFilter {
+    //keep titles of any of these types (movie, tvMovie, tvSeries, ...)
+    pub title_types: Vec<String>,
+    //keep titles that came out in or after this year
+    pub from_year: Option<u16>,
+    //keep titles that came out in or before this year
+    pub to_year: Option<u16>,
+    //keep titles with any of these genres (case-insensitive)
+    pub genres: Vec<String>,
+}
+
+impl TitleFilter {
+    //true when the filter keeps every title
+    pub fn is_empty(&self) -> bool {
+        self.title_types.is_empty() && self.from_year.is_none() && self.to_year.is_none() && self.genres.is_empty()
+    }
+
+    //whether a title passes; a title with no info (or no year, for a year range) fails any restriction on it
+    pub fn matches(&self, info: Option<&TitleInfo>) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let info = match info {
+            Some(info) => info,
+            None => return false,
+        };
+        let type_ok = self.title_types.is_empty() || self.title_types.contains(&info.title_type);
+        let year_ok = match (self.from_year, self.to_year, info.start_year) {
+            (None, None, _) => true,
+            (_, _, None) => false,
+            (from, to, Some(year)) => from.map_or(true, |f| year >= f) && to.map_or(true, |t| year <= t),
+        };
+        let genre_ok = self.genres.is_empty()
+            || info.genres.iter().any(|g| self.genres.iter().any(|wanted| wanted.eq_ignore_ascii_case(g)));
+        type_ok && year_ok && genre_ok
+    }
+
+    //a short description, e.g. "types=movie,tvMovie years=1990-2010 genres=Action" ("" for no filter)
+    //used to tell snapshots built with different filters apart
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.title_types.is_empty() {
+            parts.push(format!("types={}", self.title_types.join(",")));
+        }
+        if self.from_year.is_some() || self.to_year.is_some() {
+            let year = |y: Option<u16>| y.map(|y| y.to_string()).unwrap_or_default();
+            parts.push(format!("years={}-{}", year(self.from_year), year(self.to_year)));
+        }
+        if !self.genres.is_empty() {
+            parts.push(format!("genres={}", self.genres.join(",")));
+        }
+        parts.join(" ")
+    }
+}
+
+//keeps only the titles that pass the filter
+//input: the movie to actors map, the title table and the filter
+//output: the movie to actors map restricted to the matching titles
+pub fn filter_titles(movie_to_actors: MovieToActors, titles: &TitleTable, filter: &TitleFilter) -> MovieToActors {
+    movie_to_actors
+        .into_iter()
+        .filter(|(title, _)| filter.matches(titles.get(title)))
+        .collect()
+}
+
+//same as build_graph, but only from the titles that pass the filter, with edges dated from the title table
+//e.g. a graph of Hollywood action movies from the 2000s
+//input: the movie to actors map, the title table and the filter
+//output: the co-star graph of the matching titles
+pub fn build_graph_filtered(
+    movie_to_actors: MovieToActors,
+    titles: &TitleTable,
+    filter: &TitleFilter,
+) -> UnGraph<String, CoStar> {
+    build_graph_with_years(filter_titles(movie_to_actors, titles, filter), &title_years(titles))
+}
+
+//the titles with the biggest casts, and how many co-star edges they would add
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CastReport {
+    //(title, cast size, edges it adds), biggest first
+    pub largest: Vec<(String, usize, usize)>,
+    //edges from every title together; repeat collaborations share an edge, so the graph ends up smaller
+    pub total_edges: usize,
+    //titles over the limit, and the edges they add
+    pub over_limit: usize,
+    pub edges_over_limit: usize,
+}
+
+//sizes up every cast before the co-star graph is built, so the worst offenders can be found (and a limit
+//picked) without paying for their edges
+//input: each title with its cast size, the limit (None to only rank them) and how many titles to list
+//output: the report
+pub fn cast_report<'a>(
+    casts: impl IntoIterator<Item = (&'a str, usize)>,
+    limit: Option<&CastLimit>,
+    top: usize,
+) -> CastReport {
+    let mut largest = Vec::new();
+    let (mut total_edges, mut over_limit, mut edges_over_limit) = (0, 0, 0);
+    for (title, cast) in casts {
+        let edges = clique_edges(cast);
+        total_edges += edges;
+        if limit.is_some_and(|limit| cast > limit.max_cast) {
+            over_limit += 1;
+            edges_over_limit += edges;
+        }
+        largest.push((title.to_string(), cast, edges));
+    }
+    largest.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    largest.truncate(top);
+    CastReport { largest, total_edges, over_limit, edges_over_limit }
+}
+
 //computes the number of neighbors for each node in the graph
 //a reference to the graph is passed as input
 //the output maps each node to its number of neighbors
-pub fn degree_centrality(graph: &UnGraph<String, ()>) -> HashMap<NodeIndex, usize> {
+pub fn degree_centrality<E>(graph: &UnGraph<String, E>) -> HashMap<NodeIndex, usize> {
     graph.node_indices().map(|n| (n, graph.neighbors(n).count())).collect()
 }
 
-//computes the average distance from a node to all other nodes in the graph
+//how a power iteration ended: after how many rounds, whether the scores settled below the tolerance,
+//and how much they moved (in total) in the last round
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Convergence {
+    pub iterations: usize,
+    pub converged: bool,
+    pub change: f64,
+}
+
+//repeats x <- step(x) until x moves by less than tolerance per node
+fn power_iterate(
+    mut x: Vec<f64>,
+    tolerance: f64,
+    max_iterations: usize,
+    step: impl Fn(&[f64], &mut [f64]),
+) -> (Vec<f64>, Convergence) {
+    let n = x.len();
+    let mut next = vec![0.0; n];
+    let mut convergence = Convergence { iterations: 0, converged: n == 0, change: 0.0 };
+    while convergence.iterations < max_iterations && !convergence.converged {
+        step(&x, &mut next);
+        convergence.iterations += 1;
+        convergence.change = x.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+        convergence.converged = convergence.change < n as f64 * tolerance;
+        std::mem::swap(&mut x, &mut next);
+    }
+    (x, convergence)
+}
+
+//scales scores to unit length
+fn normalize_length(x: &mut [f64]) {
+    let norm = x.iter().map(|v| v * v).sum::<f64>().sqrt();
+    if norm > 0.0 && norm.is_finite() {
+        x.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+//computes eigenvector centrality: an actor scores highly when their co-stars score highly
+//so a minor actor in one huge ensemble cast ranks below an actor who works with well-connected people
+//uses power iteration on the adjacency matrix plus the identity (same eigenvectors, but it also settles on
+//bipartite-like graphs where plain power iteration flips back and forth)
+//input: a reference to the graph, the tolerance per node, and the maximum number of iterations
+//output: each node mapped to its score (the scores have unit length), and how the iteration went
+pub fn eigenvector_centrality<E>(
+    graph: &UnGraph<String, E>,
+    tolerance: f64,
+    max_iterations: usize,
+) -> (HashMap<NodeIndex, f64>, Convergence) {
+    let adj = adjacency(graph);
+    let n = adj.len();
+    let start = vec![1.0 / (n as f64).sqrt(); n];
+    let (x, convergence) = power_iterate(start, tolerance, max_iterations, |x, next| {
+        for (v, neighbours) in adj.iter().enumerate() {
+            next[v] = x[v] + neighbours.iter().map(|&w| x[w]).sum::<f64>();
+        }
+        normalize_length(next);
+    });
+    (graph.node_indices().map(|v| (v, x[v.index()])).collect(), convergence)
+}
+
+//computes katz centrality: every walk that reaches an actor counts, a walk of length k weighted by alpha^k
+//unlike eigenvector centrality, actors outside the largest component still get a useful score
+//alpha must be below 1 / (largest eigenvalue); anything below 1 / (largest degree) is safe
+//input: a reference to the graph, alpha, the tolerance per node, and the maximum number of iterations
+//output: each node mapped to its score (the scores have unit length), and how the iteration went
+//(if alpha is too large the scores do not settle and converged is false)
+pub fn katz_centrality<E>(
+    graph: &UnGraph<String, E>,
+    alpha: f64,
+    tolerance: f64,
+    max_iterations: usize,
+) -> (HashMap<NodeIndex, f64>, Convergence) {
+    let adj = adjacency(graph);
+    let n = adj.len();
+    let (mut x, convergence) = power_iterate(vec![0.0; n], tolerance, max_iterations, |x, next| {
+        for (v, neighbours) in adj.iter().enumerate() {
+            next[v] = 1.0 + alpha * neighbours.iter().map(|&w| x[w]).sum::<f64>();
+        }
+    });
+    normalize_length(&mut x);
+    (graph.node_indices().map(|v| (v, x[v.index()])).collect(), convergence)
+}
+
+//computes the coreness of each node: the largest k such that the node is in the k-core, the part of the
+//graph where everyone has at least k neighbours inside it
+//uses the linear-time bucket algorithm of Batagelj and Zaversnik
 //a reference to the graph is passed as input
-//the output maps each node to its closeness centrality score
-pub fn closeness_centrality(graph: &UnGraph<String, ()>) -> HashMap<NodeIndex, f64> {
-    let mut closeness = HashMap::new();
-    for node in graph.node_indices() {
-        let res = dijkstra(graph, node, None, |_| 1);
-        let total_dist: usize = res.values().sum();
-        let n = res.len();
-        if total_dist > 0 {
-            closeness.insert(node, (n - 1) as f64 / total_dist as f64);
-        } else {
-            closeness.insert(node, 0.0);
+//the output maps each node to its coreness (0 for isolated nodes)
+pub fn core_numbers<E>(graph: &UnGraph<String, E>) -> HashMap<NodeIndex, usize> {
+    let adj = adjacency(graph);
+    let n = adj.len();
+    let mut degree: Vec<usize> = adj.iter().map(|a| a.len()).collect();
+    let max_degree = degree.iter().copied().max().unwrap_or(0);
+
+    //nodes sorted by degree, with the start of each degree's block and each node's position
+    let mut start = vec![0; max_degree + 2];
+    for &d in &degree {
+        start[d + 1] += 1;
+    }
+    for d in 1..start.len() {
+        start[d] += start[d - 1];
+    }
+    let mut order = vec![0; n];
+    let mut position = vec![0; n];
+    let mut next = start.clone();
+    for v in 0..n {
+        position[v] = next[degree[v]];
+        order[position[v]] = v;
+        next[degree[v]] += 1;
+    }
+
+    //peel nodes off in order of their current degree; moving a neighbour down one degree is a swap
+    //with the first node of its block
+    for i in 0..n {
+        let v = order[i];
+        for &w in &adj[v] {
+            if degree[w] > degree[v] {
+                let dw = degree[w];
+                let first = order[start[dw]];
+                if first != w {
+                    order.swap(position[w], start[dw]);
+                    position[first] = position[w];
+                    position[w] = start[dw];
+                }
+                start[dw] += 1;
+                degree[w] -= 1;
+            }
+        }
+    }
+    graph.node_indices().map(|v| (v, degree[v.index()])).collect()
+}
+
+//a summary of a k-core decomposition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreReport {
+    //the largest coreness in the graph
+    pub max_core: usize,
+    //sizes[k] is the number of nodes in the k-core (coreness at least k), for k from 0 to max_core
+    pub sizes: Vec<usize>,
+    //the nodes of the innermost (max_core) core, sorted
+    pub innermost: Vec<NodeIndex>,
+}
+
+//summarises the output of core_numbers
+//input: the coreness of each node
+//output: the core sizes per k and the innermost core
+pub fn core_report(core: &HashMap<NodeIndex, usize>) -> CoreReport {
+    let max_core = core.values().copied().max().unwrap_or(0);
+    let mut sizes = vec![0; max_core + 1];
+    for &k in core.values() {
+        sizes[k] += 1;
+    }
+    //turn counts per coreness into counts of coreness at least k
+    for k in (0..max_core).rev() {
+        sizes[k] += sizes[k + 1];
+    }
+    let mut innermost: Vec<NodeIndex> = core.iter().filter(|(_, &k)| k == max_core).map(|(&v, _)| v).collect();
+    innermost.sort_unstable();
+    CoreReport { max_core, sizes, innermost }
+}
+
+//counts the triangles (three actors who have all worked with each other) each node is part of
+//each edge is pointed from the lower to the higher degree end, so every triangle is found exactly once and
+//no node looks at more than about sqrt(edges) out-neighbours; nodes are split across threads
+//a reference to the graph is passed as input
+//the output maps each node to its number of triangles
+pub fn triangle_counts<E>(graph: &UnGraph<String, E>) -> HashMap<NodeIndex, usize> {
+    let adj = adjacency(graph);
+    let n = adj.len();
+    let rank = |v: usize| (adj[v].len(), v);
+    let out: Vec<Vec<usize>> = adj
+        .iter()
+        .enumerate()
+        .map(|(v, neighbours)| {
+            let mut higher: Vec<usize> = neighbours.iter().copied().filter(|&w| rank(w) > rank(v)).collect();
+            higher.sort_unstable();
+            higher.dedup();
+            higher
+        })
+        .collect();
+
+    let threads = thread_count(n);
+    let chunk_size = n.div_ceil(threads).max(1);
+    let nodes: Vec<usize> = (0..n).collect();
+    let out = &out;
+    let partials: Vec<Vec<usize>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = nodes
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut counts = vec![0; n];
+                    //marked[w] == v + 1 when w is an out-neighbour of the current v
+                    let mut marked = vec![0; n];
+                    for &v in chunk {
+                        for &w in &out[v] {
+                            marked[w] = v + 1;
+                        }
+                        for &u in &out[v] {
+                            for &w in &out[u] {
+                                if marked[w] == v + 1 {
+                                    counts[v] += 1;
+                                    counts[u] += 1;
+                                    counts[w] += 1;
+                                }
+                            }
+                        }
+                    }
+                    counts
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let mut counts = vec![0; n];
+    for part in partials {
+        for v in 0..n {
+            counts[v] += part[v];
+        }
+    }
+    graph.node_indices().map(|v| (v, counts[v.index()])).collect()
+}
+
+//how many pairs of a node's neighbours there are
+fn neighbour_pairs<E>(graph: &UnGraph<String, E>, node: NodeIndex) -> usize {
+    let d = graph.neighbors(node).filter(|&w| w != node).count();
+    d * d.saturating_sub(1) / 2
+}
+
+//computes the local clustering coefficient of each node: the share of pairs of its co-stars who have also
+//worked together (1 for a tight-knit group, 0 for a hub whose co-stars never meet)
+//a reference to the graph is passed as input
+//the output maps each node to its coefficient (0 for nodes with fewer than two neighbours)
+pub fn clustering_coefficients<E>(graph: &UnGraph<String, E>) -> HashMap<NodeIndex, f64> {
+    triangle_counts(graph)
+        .into_iter()
+        .map(|(v, triangles)| {
+            let pairs = neighbour_pairs(graph, v);
+            (v, if pairs == 0 { 0.0 } else { triangles as f64 / pairs as f64 })
+        })
+        .collect()
+}
+
+//computes the average of the local clustering coefficients over all nodes
+//input: the output of clustering_coefficients
+//output: the average clustering (0 for an empty graph)
+pub fn average_clustering(coefficients: &HashMap<NodeIndex, f64>) -> f64 {
+    if coefficients.is_empty() {
+        return 0.0;
+    }
+    coefficients.values().sum::<f64>() / coefficients.len() as f64
+}
+
+//computes the global transitivity: the share of connected triples (two ties sharing an actor) that are closed
+//into triangles; unlike average_clustering, it is dominated by the well-connected actors
+//input: a reference to the graph and the output of triangle_counts
+//output: the transitivity, between 0 and 1
+pub fn transitivity<E>(graph: &UnGraph<String, E>, triangles: &HashMap<NodeIndex, usize>) -> f64 {
+    let closed: usize = triangles.values().sum();
+    let triples: usize = graph.node_indices().map(|v| neighbour_pairs(graph, v)).sum();
+    if triples == 0 { 0.0 } else { closed as f64 / triples as f64 }
+}
+
+//weighted version of degree centrality ("strength"): the total number of titles shared with co-stars
+//two actors who made 15 films together add 15, a one-off cameo adds 1
+//a reference to the graph is passed as input
+//the output maps each node to the sum of the weights of its edges
+pub fn strength_centrality(graph: &UnGraph<String, CoStar>) -> HashMap<NodeIndex, f64> {
+    graph
+        .node_indices()
+        .map(|n| (n, graph.edges(n).map(|e| e.weight().weight()).sum()))
+        .collect()
+}
+
+//settings for pagerank
+//damping is the chance of following an edge instead of jumping back to a start node; iteration stops once
+//the scores move by less than tolerance in total (or after max_iterations)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageRankOptions {
+    pub damping: f64,
+    pub tolerance: f64,
+    pub max_iterations: usize,
+}
+
+impl Default for PageRankOptions {
+    fn default() -> Self {
+        PageRankOptions { damping: 0.85, tolerance: 1e-9, max_iterations: 200 }
+    }
+}
+
+//power iteration for pagerank, where a random walker jumps back to node v with probability teleport[v]
+//edges are followed in proportion to their weight; walkers stuck on isolated nodes jump back too
+fn pagerank_with_teleport<E: Weighted>(
+    graph: &UnGraph<String, E>,
+    teleport: &[f64],
+    options: &PageRankOptions,
+) -> HashMap<NodeIndex, f64> {
+    let n = graph.node_count();
+    let strength: Vec<f64> = graph
+        .node_indices()
+        .map(|v| graph.edges(v).map(|e| e.weight().weight()).sum())
+        .collect();
+
+    let mut rank = teleport.to_vec();
+    let mut next = vec![0.0; n];
+    for _ in 0..options.max_iterations {
+        let stuck: f64 = (0..n).filter(|&v| strength[v] <= 0.0).map(|v| rank[v]).sum();
+        for v in 0..n {
+            next[v] = (1.0 - options.damping + options.damping * stuck) * teleport[v];
+        }
+        for edge in graph.edge_references() {
+            let (a, b) = (edge.source().index(), edge.target().index());
+            let w = edge.weight().weight();
+            next[b] += options.damping * rank[a] * w / strength[a];
+            next[a] += options.damping * rank[b] * w / strength[b];
+        }
+        let change: f64 = rank.iter().zip(&next).map(|(r, x)| (r - x).abs()).sum();
+        std::mem::swap(&mut rank, &mut next);
+        if change < options.tolerance {
+            break;
         }
     }
-    closeness
+    graph.node_indices().map(|v| (v, rank[v.index()])).collect()
+}
+
+//computes pagerank: how likely a random walk over the co-star graph is to be at each actor
+//collaborations with more shared titles are followed more often
+//input: a reference to the graph and the pagerank settings
+//output: each node mapped to its score; the scores add up to 1
+pub fn pagerank<E: Weighted>(graph: &UnGraph<String, E>, options: &PageRankOptions) -> HashMap<NodeIndex, f64> {
+    let n = graph.node_count();
+    pagerank_with_teleport(graph, &vec![1.0 / n as f64; n], options)
+}
+
+//computes personalized pagerank: like pagerank, but the walk always restarts at one of the seed actors
+//so high scores mark actors closely related to the seeds, including ones they never worked with directly
+//input: a reference to the graph, the seed nodes and the pagerank settings
+//output: each node mapped to its score (the seeds included); the scores add up to 1, or are all 0 without seeds
+pub fn personalized_pagerank<E: Weighted>(
+    graph: &UnGraph<String, E>,
+    seeds: &[NodeIndex],
+    options: &PageRankOptions,
+) -> HashMap<NodeIndex, f64> {
+    let mut teleport = vec![0.0; graph.node_count()];
+    let seeds: HashSet<NodeIndex> = seeds.iter().copied().collect();
+    for seed in &seeds {
+        teleport[seed.index()] = 1.0 / seeds.len() as f64;
+    }
+    pagerank_with_teleport(graph, &teleport, options)
+}
+
+//for every node: how many nodes a bfs from it reaches (itself included), the sum of the distances
+//to them, and the sum of the inverse distances
+//one bfs per node, with the nodes split across threads
+fn distance_sums(adj: &[Vec<usize>]) -> Vec<(usize, usize, f64)> {
+    let n = adj.len();
+    let threads = thread_count(n);
+    let chunk_size = n.div_ceil(threads).max(1);
+    let sources: Vec<usize> = (0..n).collect();
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = sources
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    //reused for every source; only the entries a bfs touched get reset
+                    let mut dist: Vec<usize> = vec![usize::MAX; n];
+                    let mut order: Vec<usize> = Vec::new();
+                    chunk
+                        .iter()
+                        .map(|&s| {
+                            for &v in &order {
+                                dist[v] = usize::MAX;
+                            }
+                            order.clear();
+                            dist[s] = 0;
+                            order.push(s);
+                            let (mut total, mut inverse) = (0, 0.0);
+                            let mut head = 0;
+                            while head < order.len() {
+                                let v = order[head];
+                                head += 1;
+                                for &w in &adj[v] {
+                                    if dist[w] == usize::MAX {
+                                        dist[w] = dist[v] + 1;
+                                        total += dist[w];
+                                        inverse += 1.0 / dist[w] as f64;
+                                        order.push(w);
+                                    }
+                                }
+                            }
+                            (order.len(), total, inverse)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+    })
+}
+
+//computes how close a node is to every other node, using a bfs from each node (in parallel)
+//uses the Wasserman-Faust version so disconnected graphs are handled fairly: the usual (r-1)/total_distance over
+//the r nodes a node can reach is scaled by (r-1)/(n-1), so a node in a two-actor component no longer scores 1.0
+//a reference to the graph is passed as input
+//the output maps each node to its closeness centrality score (0 for isolated nodes)
+pub fn closeness_centrality<E>(graph: &UnGraph<String, E>) -> HashMap<NodeIndex, f64> {
+    let n = graph.node_count();
+    let sums = distance_sums(&adjacency(graph));
+    graph
+        .node_indices()
+        .map(|node| {
+            let (reached, total_dist, _) = sums[node.index()];
+            let closeness = if total_dist > 0 && n > 1 {
+                let r = (reached - 1) as f64;
+                (r / (n - 1) as f64) * (r / total_dist as f64)
+            } else {
+                0.0
+            };
+            (node, closeness)
+        })
+        .collect()
+}
+
+//computes harmonic centrality: the average of 1/distance to every other node, where unreachable nodes add 0
+//this needs no special handling for disconnected graphs
+//a reference to the graph is passed as input
+//the output maps each node to its harmonic centrality score, between 0 and 1
+pub fn harmonic_centrality<E>(graph: &UnGraph<String, E>) -> HashMap<NodeIndex, f64> {
+    let n = graph.node_count();
+    let sums = distance_sums(&adjacency(graph));
+    graph
+        .node_indices()
+        .map(|node| {
+            let (_, _, inverse) = sums[node.index()];
+            (node, if n > 1 { inverse / (n - 1) as f64 } else { 0.0 })
+        })
+        .collect()
 }
 
 //takes a reference to the graph as input and returns the number of connected components
-pub fn num_connected_components(graph: &UnGraph<String, ()>) -> usize {
+pub fn num_connected_components<E>(graph: &UnGraph<String, E>) -> usize {
     connected_components(graph)
 }
 
-//maps each component/cluster to the nodes it contains
-//input: a reference to the graph
-//output: a map from component ID to a list of NodeIndexes belonging to that component
-pub fn connected_components_map(graph: &UnGraph<String, ()>) -> HashMap<usize, Vec<NodeIndex>> {
-    let mut uf = UnionFind::new(graph.node_count());
-    //union nodes connected by an edge
-    for edge in graph.edge_indices() {
-        let (a, b) = graph.edge_endpoints(edge).unwrap();
-        uf.union(a.index(), b.index());
+//maps each component/cluster to the nodes it contains
+//input: a reference to the graph
+//output: a map from component ID to a list of NodeIndexes belonging to that component
+pub fn connected_components_map<E>(graph: &UnGraph<String, E>) -> HashMap<usize, Vec<NodeIndex>> {
+    let mut uf = UnionFind::new(graph.node_count());
+    //union nodes connected by an edge
+    for edge in graph.edge_indices() {
+        let (a, b) = graph.edge_endpoints(edge).unwrap();
+        uf.union(a.index(), b.index());
+    }
+
+    //group nodes by component
+    let mut map: HashMap<usize, Vec<NodeIndex>> = HashMap::new();
+    for node in graph.node_indices() {
+        let comp = uf.find(node.index());
+        map.entry(comp).or_default().push(node);
+    }
+
+    map
+}
+
+//a split of the actors into communities
+//community maps each node to its community id (0, 1, 2, ... in order of the community's first node)
+pub struct Communities {
+    pub community: HashMap<NodeIndex, usize>,
+    pub count: usize,
+    pub modularity: f64,
+}
+
+impl Communities {
+    //the nodes of each community, indexed by community id
+    pub fn members(&self) -> Vec<Vec<NodeIndex>> {
+        let mut members = vec![Vec::new(); self.count];
+        for (&node, &c) in &self.community {
+            members[c].push(node);
+        }
+        members.iter_mut().for_each(|m| m.sort_unstable());
+        members
+    }
+}
+
+//computes the modularity of a split: the share of edge weight inside communities minus the share expected
+//if edges were placed at random (scaled by resolution)
+//input: a reference to the graph, the community of each node, and the resolution
+//output: the modularity, between -1 and 1 (0 for a graph without edges)
+pub fn modularity<E: Weighted>(
+    graph: &UnGraph<String, E>,
+    community: &HashMap<NodeIndex, usize>,
+    resolution: f64,
+) -> f64 {
+    let mut inside: HashMap<usize, f64> = HashMap::new();
+    let mut total: HashMap<usize, f64> = HashMap::new();
+    let mut m2 = 0.0;
+    for edge in graph.edge_references() {
+        let w = edge.weight().weight();
+        let (a, b) = (community[&edge.source()], community[&edge.target()]);
+        if a == b {
+            *inside.entry(a).or_default() += 2.0 * w;
+        }
+        *total.entry(a).or_default() += w;
+        *total.entry(b).or_default() += w;
+        m2 += 2.0 * w;
+    }
+    if m2 == 0.0 {
+        return 0.0;
+    }
+    total
+        .iter()
+        .map(|(c, tot)| inside.get(c).copied().unwrap_or(0.0) / m2 - resolution * (tot / m2).powi(2))
+        .sum()
+}
+
+//one level of louvain: a weighted graph whose nodes are the communities of the level below
+struct LouvainLevel {
+    //neighbours and edge weights, without self loops (each edge is listed at both ends)
+    adj: Vec<Vec<(usize, f64)>>,
+    //twice the weight of edges inside each node
+    self_weight: Vec<f64>,
+    //weighted degree of each node, self_weight included
+    degree: Vec<f64>,
+}
+
+impl LouvainLevel {
+    //moves nodes one at a time to the neighbouring community that raises modularity most, until none moves
+    //output: the community of each node, and whether anything moved
+    fn local_moving(&self, m2: f64, resolution: f64) -> (Vec<usize>, bool) {
+        let n = self.adj.len();
+        let mut community: Vec<usize> = (0..n).collect();
+        let mut total = self.degree.clone();
+        let mut weight_to = vec![0.0; n];
+        let mut touched: Vec<usize> = Vec::new();
+        let mut moved_any = false;
+        loop {
+            let mut moved = false;
+            for v in 0..n {
+                let own = community[v];
+                total[own] -= self.degree[v];
+                for &(w, weight) in &self.adj[v] {
+                    if weight_to[community[w]] == 0.0 {
+                        touched.push(community[w]);
+                    }
+                    weight_to[community[w]] += weight;
+                }
+                //gain of joining c, up to a term that is the same for every c
+                let gain = |c: usize, weight_to: &[f64]| weight_to[c] - resolution * total[c] * self.degree[v] / m2;
+                let mut best = (own, gain(own, &weight_to));
+                for &c in &touched {
+                    let g = gain(c, &weight_to);
+                    //only strictly better moves, so ties keep the node where it is and the loop ends
+                    if g > best.1 + 1e-12 {
+                        best = (c, g);
+                    }
+                }
+                for &c in &touched {
+                    weight_to[c] = 0.0;
+                }
+                touched.clear();
+                total[best.0] += self.degree[v];
+                if best.0 != own {
+                    community[v] = best.0;
+                    moved = true;
+                    moved_any = true;
+                }
+            }
+            if !moved {
+                break;
+            }
+        }
+        (community, moved_any)
+    }
+
+    //merges each community into one node
+    //input: the community of each node, numbered 0..count
+    fn aggregate(&self, community: &[usize], count: usize) -> LouvainLevel {
+        let mut edges: Vec<HashMap<usize, f64>> = vec![HashMap::new(); count];
+        let mut self_weight = vec![0.0; count];
+        let mut degree = vec![0.0; count];
+        for (v, neighbours) in self.adj.iter().enumerate() {
+            let c = community[v];
+            self_weight[c] += self.self_weight[v];
+            degree[c] += self.degree[v];
+            for &(w, weight) in neighbours {
+                if community[w] == c {
+                    self_weight[c] += weight; //each inside edge is seen from both ends, so counts twice
+                } else {
+                    *edges[c].entry(community[w]).or_default() += weight;
+                }
+            }
+        }
+        let adj = edges
+            .into_iter()
+            .map(|e| {
+                let mut e: Vec<(usize, f64)> = e.into_iter().collect();
+                e.sort_unstable_by_key(|&(w, _)| w);
+                e
+            })
+            .collect();
+        LouvainLevel { adj, self_weight, degree }
+    }
+}
+
+//renumbers labels to 0, 1, 2, ... in order of first appearance
+//output: the new labels and how many there are
+fn renumber(labels: &[usize]) -> (Vec<usize>, usize) {
+    let mut ids: HashMap<usize, usize> = HashMap::new();
+    let renumbered = labels
+        .iter()
+        .map(|l| {
+            let next = ids.len();
+            *ids.entry(*l).or_insert(next)
+        })
+        .collect();
+    (renumbered, ids.len())
+}
+
+//finds communities (groups of actors who mostly work with each other) with the louvain method
+//edges are weighted by the number of shared titles
+//resolution 1.0 is standard modularity; higher values find more, smaller communities (e.g. franchises),
+//lower values fewer, larger ones (e.g. whole film industries)
+//input: a reference to the graph and the resolution
+//output: the community of each node, and the modularity of the split at that resolution
+pub fn louvain_communities<E: Weighted>(graph: &UnGraph<String, E>, resolution: f64) -> Communities {
+    let n = graph.node_count();
+    let mut adj: Vec<Vec<(usize, f64)>> = vec![Vec::new(); n];
+    let mut self_weight = vec![0.0; n];
+    for edge in graph.edge_references() {
+        let (a, b, w) = (edge.source().index(), edge.target().index(), edge.weight().weight());
+        if a == b {
+            self_weight[a] += 2.0 * w;
+        } else {
+            adj[a].push((b, w));
+            adj[b].push((a, w));
+        }
+    }
+    let degree: Vec<f64> =
+        (0..n).map(|v| self_weight[v] + adj[v].iter().map(|&(_, w)| w).sum::<f64>()).collect();
+    let m2: f64 = degree.iter().sum();
+
+    //community of each original node at the current level
+    let mut membership: Vec<usize> = (0..n).collect();
+    let mut level = LouvainLevel { adj, self_weight, degree };
+    if m2 > 0.0 {
+        loop {
+            let (community, moved) = level.local_moving(m2, resolution);
+            if !moved {
+                break;
+            }
+            let (community, count) = renumber(&community);
+            membership.iter_mut().for_each(|c| *c = community[*c]);
+            level = level.aggregate(&community, count);
+        }
+    }
+
+    let (membership, count) = renumber(&membership);
+    let community: HashMap<NodeIndex, usize> = graph.node_indices().map(|v| (v, membership[v.index()])).collect();
+    let modularity = modularity(graph, &community, resolution);
+    Communities { community, count, modularity }
+}
+
+//label propagation gives up after this many rounds (it usually settles in well under 20)
+const LABEL_PROPAGATION_MAX_ROUNDS: usize = 100;
+
+//finds communities with asynchronous label propagation, which is cheap enough for the full graph
+//every actor starts with their own label, then, visiting actors in a random order each round, takes the label
+//most of their co-stars have (ties broken at random, keeping the current label when it is among them)
+//until every actor already has such a label; each round is linear in the number of edges
+//input: reference to the graph, and a mutable random number generator (seed it for repeatable runs)
+//output: a map from community ID to a list of NodeIndexes belonging to that community
+pub fn label_propagation<E>(graph: &UnGraph<String, E>, rng: &mut impl Rng) -> HashMap<usize, Vec<NodeIndex>> {
+    let adj = adjacency(graph);
+    let n = adj.len();
+    let mut label: Vec<usize> = (0..n).collect();
+    let mut order: Vec<usize> = (0..n).collect();
+    //how many neighbours have each label, reset after every node
+    let mut count = vec![0usize; n];
+    let mut seen: Vec<usize> = Vec::new();
+    let mut best: Vec<usize> = Vec::new();
+
+    for _ in 0..LABEL_PROPAGATION_MAX_ROUNDS {
+        order.shuffle(rng);
+        let mut changed = false;
+        for &v in &order {
+            if adj[v].is_empty() {
+                continue;
+            }
+            for &w in &adj[v] {
+                if count[label[w]] == 0 {
+                    seen.push(label[w]);
+                }
+                count[label[w]] += 1;
+            }
+            let max = seen.iter().map(|&l| count[l]).max().unwrap_or(0);
+            best.extend(seen.iter().copied().filter(|&l| count[l] == max));
+            if !best.contains(&label[v]) {
+                label[v] = best[rng.gen_range(0..best.len())];
+                changed = true;
+            }
+            for &l in &seen {
+                count[l] = 0;
+            }
+            seen.clear();
+            best.clear();
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut map: HashMap<usize, Vec<NodeIndex>> = HashMap::new();
+    for node in graph.node_indices() {
+        map.entry(label[node.index()]).or_default().push(node);
+    }
+    map
+}
+
+//depth-first search numbering used to find cut actors and cut edges (tarjan's low-link method)
+struct LowLink {
+    //preorder number of each node; the nodes of a dfs subtree get consecutive numbers
+    disc: Vec<usize>,
+    //lowest preorder number reachable from the node's subtree using one non-tree edge
+    low: Vec<usize>,
+    //number of nodes in the node's dfs subtree
+    subtree: Vec<usize>,
+    //the dfs parent of each node and the edge to it (None for the first node of a component)
+    parent: Vec<Option<(usize, usize)>>,
+    //size of the connected component each node is in
+    component: Vec<usize>,
+    //neighbours of each node with the edge index
+    adj: Vec<Vec<(usize, usize)>>,
+}
+
+impl LowLink {
+    //iterative, so the million-node components of the full graph cannot overflow the stack
+    fn new<E>(graph: &UnGraph<String, E>) -> Self {
+        let n = graph.node_count();
+        let mut adj: Vec<Vec<(usize, usize)>> = vec![Vec::new(); n];
+        for edge in graph.edge_references() {
+            let (a, b) = (edge.source().index(), edge.target().index());
+            if a != b {
+                adj[a].push((b, edge.id().index()));
+                adj[b].push((a, edge.id().index()));
+            }
+        }
+        let mut disc = vec![usize::MAX; n];
+        let mut low = vec![0; n];
+        let mut subtree = vec![1; n];
+        let mut parent: Vec<Option<(usize, usize)>> = vec![None; n];
+        let mut component = vec![0; n];
+        let mut order: Vec<usize> = Vec::with_capacity(n);
+        //(node, position in its neighbour list)
+        let mut stack: Vec<(usize, usize)> = Vec::new();
+
+        for root in 0..n {
+            if disc[root] != usize::MAX {
+                continue;
+            }
+            let first = order.len();
+            disc[root] = order.len();
+            low[root] = disc[root];
+            order.push(root);
+            stack.push((root, 0));
+            while let Some((v, i)) = stack.pop() {
+                if let Some(&(w, edge)) = adj[v].get(i) {
+                    stack.push((v, i + 1));
+                    if disc[w] == usize::MAX {
+                        disc[w] = order.len();
+                        low[w] = disc[w];
+                        parent[w] = Some((v, edge));
+                        order.push(w);
+                        stack.push((w, 0));
+                    } else if parent[v].map(|(_, e)| e) != Some(edge) {
+                        low[v] = low[v].min(disc[w]);
+                    }
+                } else if let Some((p, _)) = parent[v] {
+                    //v is finished
+                    low[p] = low[p].min(low[v]);
+                    subtree[p] += subtree[v];
+                }
+            }
+            for &v in &order[first..] {
+                component[v] = order.len() - first;
+            }
+        }
+        LowLink { disc, low, subtree, parent, component, adj }
+    }
+
+    //whether w is in the dfs subtree of c
+    fn in_subtree(&self, c: usize, w: usize) -> bool {
+        self.disc[w] >= self.disc[c] && self.disc[w] < self.disc[c] + self.subtree[c]
+    }
+}
+
+//one of the components a connected component falls into when an actor is removed
+//neighbours are the removed actor's co-stars in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CutPiece {
+    pub size: usize,
+    pub neighbours: Vec<NodeIndex>,
+}
+
+//an actor whose removal splits their component, and the pieces it splits into (largest first)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArticulationPoint {
+    pub node: NodeIndex,
+    pub pieces: Vec<CutPiece>,
+}
+
+impl ArticulationPoint {
+    //how many actors lose their connection to the largest piece
+    pub fn split_off(&self) -> usize {
+        self.pieces.iter().skip(1).map(|p| p.size).sum()
+    }
+}
+
+//finds the actors who are the only link between groups of actors (articulation points)
+//deterministic and linear in the number of edges
+//input: a reference to the graph
+//output: every articulation point with the pieces its removal leaves behind, the ones that cut off most
+//actors from the rest first
+pub fn articulation_points<E>(graph: &UnGraph<String, E>) -> Vec<ArticulationPoint> {
+    let lowlink = LowLink::new(graph);
+    //children whose subtree has no other way back above their parent become separate pieces
+    let mut separated: Vec<Vec<usize>> = vec![Vec::new(); graph.node_count()];
+    for (c, parent) in lowlink.parent.iter().enumerate() {
+        if let Some((p, _)) = *parent {
+            if lowlink.low[c] >= lowlink.disc[p] {
+                separated[p].push(c);
+            }
+        }
+    }
+
+    //children in preorder, so the piece holding a neighbour can be found by binary search
+    for children in &mut separated {
+        children.sort_unstable_by_key(|&c| lowlink.disc[c]);
+    }
+
+    let mut points: Vec<ArticulationPoint> = separated
+        .iter()
+        .enumerate()
+        .filter(|(v, children)| children.len() + usize::from(lowlink.parent[*v].is_some()) >= 2)
+        .map(|(v, children)| {
+            let mut pieces: Vec<CutPiece> = children
+                .iter()
+                .map(|&c| CutPiece { size: lowlink.subtree[c], neighbours: Vec::new() })
+                .collect();
+            //everything else: the part of the component above v
+            let rest = lowlink.component[v] - 1 - pieces.iter().map(|p| p.size).sum::<usize>();
+            let mut above = CutPiece { size: rest, neighbours: Vec::new() };
+            for &(w, _) in &lowlink.adj[v] {
+                let neighbour = NodeIndex::new(w);
+                let i = children.partition_point(|&c| lowlink.disc[c] <= lowlink.disc[w]);
+                match i.checked_sub(1).filter(|&i| lowlink.in_subtree(children[i], w)) {
+                    Some(i) => pieces[i].neighbours.push(neighbour),
+                    None => above.neighbours.push(neighbour),
+                }
+            }
+            if rest > 0 {
+                pieces.push(above);
+            }
+            for piece in &mut pieces {
+                piece.neighbours.sort_unstable();
+                piece.neighbours.dedup();
+            }
+            pieces.sort_by(|a, b| b.size.cmp(&a.size).then(a.neighbours.cmp(&b.neighbours)));
+            ArticulationPoint { node: NodeIndex::new(v), pieces }
+        })
+        .collect();
+    points.sort_by(|a, b| b.split_off().cmp(&a.split_off()).then(a.node.cmp(&b.node)));
+    points
+}
+
+//a collaboration whose removal splits its component in two
+//sizes are how many actors stay connected to each end
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bridge {
+    pub edge: EdgeIndex,
+    pub ends: (NodeIndex, NodeIndex),
+    pub sizes: (usize, usize),
+}
+
+//finds the collaborations that are the only link between groups of actors (bridges)
+//deterministic and linear in the number of edges
+//input: a reference to the graph
+//output: every bridge, the ones with the largest smaller side first
+pub fn bridges<E>(graph: &UnGraph<String, E>) -> Vec<Bridge> {
+    let lowlink = LowLink::new(graph);
+    let mut bridges: Vec<Bridge> = lowlink
+        .parent
+        .iter()
+        .enumerate()
+        .filter_map(|(c, parent)| {
+            let (p, edge) = (*parent)?;
+            (lowlink.low[c] > lowlink.disc[p]).then(|| Bridge {
+                edge: EdgeIndex::new(edge),
+                ends: (NodeIndex::new(p), NodeIndex::new(c)),
+                sizes: (lowlink.component[c] - lowlink.subtree[c], lowlink.subtree[c]),
+            })
+        })
+        .collect();
+    let smaller = |b: &Bridge| b.sizes.0.min(b.sizes.1);
+    bridges.sort_by(|a, b| smaller(b).cmp(&smaller(a)).then(a.edge.cmp(&b.edge)));
+    bridges
+}
+
+//finds the shortest path between two nodes
+//input: a reference to the graph, the id of the first actor, the id of the second actor
+//output: the length of the path between them, if it exists 
+pub fn shortest_path_length<E>(
+    graph: &UnGraph<String, E>,
+    from: &str,
+    to: &str,
+) -> Option<usize> {
+    //map actor name to node indicies 
+    let node_map: HashMap<String, NodeIndex> = graph
+        .node_references()
+        .map(|(idx, name)| (name.clone(), idx))
+        .collect();
+    let start = node_map.get(from)?;
+    let end = node_map.get(to)?;
+
+    //using dijkstra to find shortest path
+    let res = dijkstra(graph, *start, Some(*end), |_| 1);
+    res.get(end).copied()
+}
+
+//one hop of a path: two actors and the titles they made together
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathStep {
+    pub from: NodeIndex,
+    pub to: NodeIndex,
+    pub titles: Vec<String>,
+}
+
+//finds the actual chain of actors between two actors (a "Bacon path"), not just its length
+//input: a reference to the graph, the id of the first actor, the id of the second actor
+//output: one PathStep per hop, in order from `from` to `to`, if a path exists
+//(an empty vec means from and to are the same actor)
+pub fn shortest_path(
+    graph: &UnGraph<String, CoStar>,
+    actor_id_map: &HashMap<String, NodeIndex>,
+    from: &str,
+    to: &str,
+) -> Option<Vec<PathStep>> {
+    let start = *actor_id_map.get(from)?;
+    let end = *actor_id_map.get(to)?;
+
+    //bfs from start, remembering the edge we arrived by
+    let mut came_from: HashMap<NodeIndex, (NodeIndex, petgraph::graph::EdgeIndex)> = HashMap::new();
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    visited.insert(start);
+    queue.push_back(start);
+    while let Some(node) = queue.pop_front() {
+        if node == end {
+            break;
+        }
+        for edge in graph.edges(node) {
+            let neighbor = edge.target();
+            if visited.insert(neighbor) {
+                came_from.insert(neighbor, (node, edge.id()));
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    if !visited.contains(&end) {
+        return None;
+    }
+
+    //walk back from the end to rebuild the path
+    let mut steps = Vec::new();
+    let mut current = end;
+    while current != start {
+        let (prev, edge) = came_from[&current];
+        steps.push(PathStep { from: prev, to: current, titles: graph[edge].titles.clone() });
+        current = prev;
+    }
+    steps.reverse();
+    Some(steps)
+}
+
+//renders a path as a sentence, e.g. "A was in tt1 with B, who was in tt2 with C"
+//input: the graph the path came from, the path, and the actor id to name map
+//output: the sentence (actors without a known name are shown by id)
+pub fn describe_path(
+    graph: &UnGraph<String, CoStar>,
+    path: &[PathStep],
+    actor_id_to_name: &HashMap<String, String>,
+) -> String {
+    let name = |node: NodeIndex| {
+        let id = &graph[node];
+        actor_id_to_name.get(id).unwrap_or(id).clone()
+    };
+    let mut text = String::new();
+    for (i, step) in path.iter().enumerate() {
+        let titles = describe_titles(&step.titles);
+        if i == 0 {
+            text.push_str(&format!("{} was in {} with {}", name(step.from), titles, name(step.to)));
+        } else {
+            text.push_str(&format!(", who was in {} with {}", titles, name(step.to)));
+        }
+    }
+    text
+}
+
+//the titles linking one hop of a path, e.g. "tt1", "tt1 and tt2" or "tt1 and 4 other titles"
+pub fn describe_titles(titles: &[String]) -> String {
+    match titles.len() {
+        0 => "something".to_string(),
+        1 | 2 => titles.join(" and "),
+        n => format!("{} and {} other titles", titles[0], n - 1),
+    }
+}
+
+//uses bfs to create a subgraph surrounding a particular actor
+//input: a reference to the full graph, the actor id mappings, the actor name, and the depth(how many steps from the actor)
+//output: an undirected subgraph containing the actor and neighbors within the given depth,
+//with every edge of the full graph between them (so co-stars of the actor who worked with each other stay linked)
+pub fn extract_subgraph_around_actor<E: Clone>(
+    graph: &UnGraph<String, E>,
+    actor_id_map: &HashMap<String, NodeIndex>,
+    actor_name: &str,
+    max_depth: usize,
+) -> UnGraph<String, E> {
+    match ego_network(graph, actor_id_map, actor_name, max_depth) {
+        Some(ego) => ego.graph,
+        None => UnGraph::<String, E>::new_undirected(), // actor not found
+    }
+}
+
+//an actor's ego network: the induced subgraph within some radius of them, plus summary metrics
+pub struct EgoNetwork<E> {
+    pub graph: UnGraph<String, E>,
+    //the actor at the centre, as an index into `graph`
+    pub ego: NodeIndex,
+    pub metrics: EgoMetrics,
+}
+
+//metrics describing how the ego's contacts are tied to each other
+#[derive(Debug, Clone, PartialEq)]
+pub struct EgoMetrics {
+    //number of direct neighbours (alters) of the ego
+    pub alters: usize,
+    //number of edges between alters
+    pub alter_ties: usize,
+    //alter_ties divided by the number of possible alter pairs (0 with fewer than two alters)
+    pub density: f64,
+    //Burt's effective size: alters minus the average number of ties each alter has to other alters
+    pub effective_size: f64,
+    //how many groups the rest of the network falls into once the ego is removed
+    pub alter_components: usize,
+}
+
+//computes ego metrics for a node of a graph (usually the ego of an ego network)
+//input: a reference to the graph and the ego node
+//output: the metrics; alter_components counts components of the graph minus the ego
+pub fn ego_metrics<E>(graph: &UnGraph<String, E>, ego: NodeIndex) -> EgoMetrics {
+    let alters: HashSet<NodeIndex> = graph.neighbors(ego).filter(|&n| n != ego).collect();
+    let n = alters.len();
+    let alter_ties = graph
+        .edge_references()
+        .filter(|e| e.source() != e.target() && alters.contains(&e.source()) && alters.contains(&e.target()))
+        .count();
+
+    let density = if n < 2 { 0.0 } else { alter_ties as f64 / (n * (n - 1) / 2) as f64 };
+    let effective_size = if n == 0 { 0.0 } else { n as f64 - 2.0 * alter_ties as f64 / n as f64 };
+
+    //union everything except the ego, then count the roots of the remaining nodes
+    let mut uf = UnionFind::new(graph.node_count());
+    for edge in graph.edge_references() {
+        if edge.source() != ego && edge.target() != ego {
+            uf.union(edge.source().index(), edge.target().index());
+        }
+    }
+    let alter_components = graph
+        .node_indices()
+        .filter(|&v| v != ego)
+        .map(|v| uf.find(v.index()))
+        .collect::<HashSet<_>>()
+        .len();
+
+    EgoMetrics { alters: n, alter_ties, density, effective_size, alter_components }
+}
+
+//Burt's structural hole measures for one actor, weighted by shared titles
+//high effective size / efficiency and low constraint mark brokers whose contacts don't know each other
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StructuralHoles {
+    //contacts minus how redundant they are with each other (equals EgoMetrics::effective_size without weights)
+    pub effective_size: f64,
+    //effective size divided by the number of contacts, between 0 and 1
+    pub efficiency: f64,
+    //how much the actor's time is tied up in contacts who are tied to each other, up to about 1.1
+    pub constraint: f64,
+    //how much of the constraint comes from a single contact (0 when it is spread evenly), between 0 and 1
+    pub hierarchy: f64,
+}
+
+//computes the structural hole measures of one actor from their contacts and the ties between them
+//input: a reference to the graph and the actor
+//output: the measures, or None for an actor without contacts
+pub fn ego_structural_holes<E: Weighted>(graph: &UnGraph<String, E>, ego: NodeIndex) -> Option<StructuralHoles> {
+    //tie strength between each pair of neighbours, summed over parallel edges
+    let ties = |v: NodeIndex| {
+        let mut ties: HashMap<NodeIndex, f64> = HashMap::new();
+        for edge in graph.edges(v) {
+            if edge.target() != v {
+                *ties.entry(edge.target()).or_default() += edge.weight().weight();
+            }
+        }
+        ties
+    };
+    let contacts = ties(ego);
+    if contacts.is_empty() {
+        return None;
     }
+    //share of the ego's (p_iq) and of each contact's (p_qj) ties spent on one contact, and each contact's
+    //strongest tie (for the redundancy m_jq)
+    let strength = |ties: &HashMap<NodeIndex, f64>| ties.values().sum::<f64>();
+    let ego_strength = strength(&contacts);
+    let contact_ties: HashMap<NodeIndex, HashMap<NodeIndex, f64>> = contacts.keys().map(|&j| (j, ties(j))).collect();
+    let contact_strength: HashMap<NodeIndex, f64> = contact_ties.iter().map(|(&j, t)| (j, strength(t))).collect();
 
-    //group nodes by component
-    let mut map: HashMap<usize, Vec<NodeIndex>> = HashMap::new();
-    for node in graph.node_indices() {
-        let comp = uf.find(node.index());
-        map.entry(comp).or_default().push(node);
+    let mut sorted_contacts: Vec<NodeIndex> = contacts.keys().copied().collect();
+    sorted_contacts.sort_unstable();
+    let mut effective_size = 0.0;
+    let mut dyadic = Vec::with_capacity(contacts.len());
+    for &j in &sorted_contacts {
+        let j_ties = &contact_ties[&j];
+        let j_max = j_ties.values().copied().fold(0.0, f64::max);
+        let mut redundancy = 0.0;
+        let mut indirect = 0.0;
+        for (&q, &w_jq) in j_ties {
+            if let Some(&w_iq) = contacts.get(&q) {
+                let p_iq = w_iq / ego_strength;
+                redundancy += p_iq * w_jq / j_max;
+                indirect += p_iq * w_jq / contact_strength[&q];
+            }
+        }
+        effective_size += 1.0 - redundancy;
+        dyadic.push((contacts[&j] / ego_strength + indirect).powi(2));
     }
 
-    map
+    let n = contacts.len() as f64;
+    let constraint: f64 = dyadic.iter().sum();
+    let hierarchy = if contacts.len() < 2 || constraint == 0.0 {
+        0.0
+    } else {
+        let mean = constraint / n;
+        dyadic.iter().filter(|&&c| c > 0.0).map(|c| (c / mean) * (c / mean).ln()).sum::<f64>() / (n * n.ln())
+    };
+    Some(StructuralHoles { effective_size, efficiency: effective_size / n, constraint, hierarchy })
 }
 
-//finds the shortest path between two nodes
-//input: a reference to the graph, the id of the first actor, the id of the second actor
-//output: the length of the path between them, if it exists 
-pub fn shortest_path_length(
-    graph: &UnGraph<String, ()>,
-    from: &str,
-    to: &str,
-) -> Option<usize> {
-    //map actor name to node indicies 
-    let node_map: HashMap<String, NodeIndex> = graph
-        .node_references()
-        .map(|(idx, name)| (name.clone(), idx))
-        .collect();
-    let start = node_map.get(from)?;
-    let end = node_map.get(to)?;
+//computes the structural hole measures of every actor with at least one contact
+//input: a reference to the graph (e.g. an ego network or a sample)
+//output: each actor mapped to their measures
+pub fn structural_holes<E: Weighted>(graph: &UnGraph<String, E>) -> HashMap<NodeIndex, StructuralHoles> {
+    graph
+        .node_indices()
+        .filter_map(|v| ego_structural_holes(graph, v).map(|holes| (v, holes)))
+        .collect()
+}
 
-    //using dijkstra to find shortest path
-    let res = dijkstra(graph, *start, Some(*end), |_| 1);
-    res.get(end).copied()
+impl<E: Weighted> EgoNetwork<E> {
+    //the structural hole measures of the ego within its ego network
+    pub fn structural_holes(&self) -> Option<StructuralHoles> {
+        ego_structural_holes(&self.graph, self.ego)
+    }
 }
 
-//uses bfs to create a subgraph surrounding a particular actor
-//input: a reference to the full graph, the actor id mappings, the actor name, and the depth(how many steps from the actor)
-//output: an undirected subgraph containing the actor and neighbors within the given depth
-pub fn extract_subgraph_around_actor(
-    graph: &UnGraph<String, ()>,
+//builds the induced ego network of an actor
+//input: a reference to the full graph, the actor id mappings, the actor id, and the radius (how many steps from the actor)
+//output: the subgraph of everyone within the radius with all edges between them, and its ego metrics
+//(None if the actor isn't in the graph)
+pub fn ego_network<E: Clone>(
+    graph: &UnGraph<String, E>,
     actor_id_map: &HashMap<String, NodeIndex>,
-    actor_name: &str,
-    max_depth: usize,
-) -> UnGraph<String, ()> {
-    let mut subgraph = UnGraph::<String, ()>::new_undirected();
+    actor_id: &str,
+    radius: usize,
+) -> Option<EgoNetwork<E>> {
+    let start = *actor_id_map.get(actor_id)?;
+
+    //bfs out to the radius to find the members
+    let mut members = vec![start];
     let mut visited = HashSet::new();
     let mut queue = VecDeque::new();
-    let mut node_map = HashMap::new(); // maps original node indices to subgraph indices
-
-    let start = match actor_id_map.get(actor_name) {
-        Some(idx) => *idx,
-        None => return subgraph, // actor not found
-    };
-
-    queue.push_back((start, 0));
     visited.insert(start);
-
-    // add starting node
-    let sub_start = subgraph.add_node(graph[start].clone());
-    node_map.insert(start, sub_start);
-
+    queue.push_back((start, 0));
     while let Some((node, depth)) = queue.pop_front() {
-        if depth >= max_depth {
+        if depth >= radius {
             continue;
         }
-
         for neighbor in graph.neighbors(node) { //visit each neighbor only once
             if visited.insert(neighbor) {
+                members.push(neighbor);
                 queue.push_back((neighbor, depth + 1));
             }
+        }
+    }
 
-            // add neighbor if its within max_depth
-            if depth + 1 <= max_depth {
-                let sub_node = *node_map.entry(node)
-                    .or_insert_with(|| subgraph.add_node(graph[node].clone()));
-                let sub_neighbor = *node_map.entry(neighbor)
-                    .or_insert_with(|| subgraph.add_node(graph[neighbor].clone()));
-
-                if !subgraph.contains_edge(sub_node, sub_neighbor) {
-                    subgraph.add_edge(sub_node, sub_neighbor, ());
+    //copy the members, then every edge between two members
+    let mut subgraph = UnGraph::<String, E>::new_undirected();
+    let mut node_map = HashMap::new(); // maps original node indices to subgraph indices
+    for &node in &members {
+        node_map.insert(node, subgraph.add_node(graph[node].clone()));
+    }
+    for &node in &members {
+        for edge in graph.edges(node) {
+            let neighbor = edge.target();
+            //each undirected edge is seen from both ends, so only add it from the lower index
+            if node.index() <= neighbor.index() {
+                if let Some(&sub_neighbor) = node_map.get(&neighbor) {
+                    subgraph.add_edge(node_map[&node], sub_neighbor, edge.weight().clone());
                 }
             }
         }
     }
 
-    subgraph
+    let ego = node_map[&start];
+    let metrics = ego_metrics(&subgraph, ego);
+    Some(EgoNetwork { graph: subgraph, ego, metrics })
 }
 
 //randomly sample a set of nodes and builds a subgraph containing only them
 //input: reference to the graph, sample size, and a mutable random number generator
 //output: subgraph of the sampled nodes
-pub fn random_actor_subgraph(
-    graph: &UnGraph<String, ()>,
+pub fn random_actor_subgraph<E: Clone>(
+    graph: &UnGraph<String, E>,
     sample_size: usize,
     rng: &mut impl Rng,
-) -> UnGraph<String, ()> {
+) -> UnGraph<String, E> {
     let all_nodes: Vec<_> = graph.node_indices().collect();
     //randomly sample sample_size nodes
     let sampled_nodes: HashSet<_> = all_nodes
@@ -175,7 +1586,7 @@ pub fn random_actor_subgraph(
         .into_iter()
         .collect();
 
-    let mut subgraph = UnGraph::<String, ()>::new_undirected();
+    let mut subgraph = UnGraph::<String, E>::new_undirected();
     let mut node_map = HashMap::new();
 
     //add sampled nodes to new subgraph
@@ -186,12 +1597,13 @@ pub fn random_actor_subgraph(
 
     //add edges that exist in the original graph
     for &node in &sampled_nodes {
-        for neighbor in graph.neighbors(node) {
+        for edge in graph.edges(node) {
+            let neighbor = edge.target();
             if sampled_nodes.contains(&neighbor) {
                 let a = node_map[&node];
                 let b = node_map[&neighbor];
                 if !subgraph.contains_edge(a, b) {
-                    subgraph.add_edge(a, b, ());
+                    subgraph.add_edge(a, b, edge.weight().clone());
                 }
             }
         }
@@ -200,79 +1612,233 @@ pub fn random_actor_subgraph(
     subgraph
 }
 
-//computes betweeness centrality for all nodes of the graph
-//input: reference to a graph
-//output: hashmap of the nodeidex to the centrality score
-pub fn betweenness_centrality(
-    graph: &UnGraph<String, ()>,
-) -> HashMap<NodeIndex, f64> {
-    let mut bc = HashMap::new();
-    for node in graph.node_indices() {
-        bc.insert(node, 0.0);
+//the neighbours of every node as plain indices, so hot loops can index vectors instead of hashing
+//node indices of a petgraph Graph are always 0..node_count, which is what makes this possible
+pub fn adjacency<E>(graph: &UnGraph<String, E>) -> Vec<Vec<usize>> {
+    graph
+        .node_indices()
+        .map(|v| graph.neighbors(v).map(|w| w.index()).filter(|&w| w != v.index()).collect())
+        .collect()
+}
+
+//how many worker threads to split `jobs` pieces of work across
+pub(crate) fn thread_count(jobs: usize) -> usize {
+    let available = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    available.min(jobs).max(1)
+}
+
+//per-thread scratch space for Brandes' algorithm, allocated once and reused for every source
+//sigma (the number of shortest paths) is a float: path counts through dense casts overflow any integer type
+struct BrandesWorkspace {
+    dist: Vec<i64>,
+    sigma: Vec<f64>,
+    delta: Vec<f64>,
+    //nodes in the order bfs reached them, which is also the list of entries to reset afterwards
+    order: Vec<usize>,
+}
+
+impl BrandesWorkspace {
+    fn new(n: usize) -> Self {
+        BrandesWorkspace { dist: vec![-1; n], sigma: vec![0.0; n], delta: vec![0.0; n], order: Vec::new() }
     }
 
-    for s in graph.node_indices() {
-        let mut stack = Vec::new();
-        let mut pred: HashMap<NodeIndex, Vec<NodeIndex>> = HashMap::new();
-        let mut sigma: HashMap<NodeIndex, usize> = HashMap::new();
-        let mut dist: HashMap<NodeIndex, isize> = HashMap::new();
+    //runs one bfs from s and back-propagates dependencies (Brandes' algorithm)
+    //afterwards delta[w] is how much s's shortest paths depend on passing through w (0 for s itself),
+    //for every w in `order`; everything else is untouched
+    fn run(&mut self, adj: &[Vec<usize>], s: usize) {
+        //reset only what the previous source touched
+        for &v in &self.order {
+            self.dist[v] = -1;
+            self.sigma[v] = 0.0;
+            self.delta[v] = 0.0;
+        }
+        self.order.clear();
 
-        for v in graph.node_indices() {
-            pred.insert(v, Vec::new());
-            sigma.insert(v, 0);
-            dist.insert(v, -1);
-        }
-        sigma.insert(s, 1);
-        dist.insert(s, 0);
-
-        //BFS traversal to calculate shortest paths
-        let mut queue = VecDeque::new();
-        queue.push_back(s);
-        while let Some(v) = queue.pop_front() {
-            stack.push(v);
-            let d = dist[&v];
-            for w in graph.neighbors(v) {
-                if dist[&w] < 0 {
-                    queue.push_back(w);
-                    dist.insert(w, d + 1);
+        //BFS traversal to calculate shortest paths (order doubles as the queue)
+        self.dist[s] = 0;
+        self.sigma[s] = 1.0;
+        self.order.push(s);
+        let mut head = 0;
+        while head < self.order.len() {
+            let v = self.order[head];
+            head += 1;
+            let d = self.dist[v];
+            for &w in &adj[v] {
+                if self.dist[w] < 0 {
+                    self.dist[w] = d + 1;
+                    self.order.push(w);
                 }
-                if dist[&w] == d + 1 {
-                    sigma.insert(w, sigma[&w] + sigma[&v]);
-                    pred.get_mut(&w).unwrap().push(v);
+                if self.dist[w] == d + 1 {
+                    self.sigma[w] += self.sigma[v];
                 }
             }
         }
 
-        //back-propagation of dependencies
-        //for centrality scores
-        let mut delta: HashMap<NodeIndex, f64> = HashMap::new();
-        for v in graph.node_indices() {
-            delta.insert(v, 0.0);
+        //back-propagation of dependencies, farthest nodes first
+        //predecessors of w are the neighbours one step closer to s, so no predecessor lists are needed
+        for &w in self.order.iter().rev() {
+            let coeff = (1.0 + self.delta[w]) / self.sigma[w];
+            let dw = self.dist[w];
+            for &v in &adj[w] {
+                if self.dist[v] == dw - 1 {
+                    self.delta[v] += self.sigma[v] * coeff;
+                }
+            }
         }
+        self.delta[s] = 0.0;
+    }
+}
 
-        while let Some(w) = stack.pop() {
-            for v in &pred[&w] {
-                let c = (sigma[v] as f64 / sigma[&w] as f64) * (1.0 + delta[&w]);
-                delta.insert(*v, delta[&v] + c);
-            }
-            if w != s {
-                bc.insert(w, bc[&w] + delta[&w]);
-            }
+//runs Brandes from every source, split across threads, each with its own workspace and accumulators
+//output: for every node, the sum over sources of delta * scale, and the sum of (delta * scale)^2
+fn accumulate_dependencies(adj: &[Vec<usize>], sources: &[usize], scale: f64) -> (Vec<f64>, Vec<f64>) {
+    let n = adj.len();
+    let threads = thread_count(sources.len());
+    let chunk_size = sources.len().div_ceil(threads).max(1);
+
+    let partials: Vec<(Vec<f64>, Vec<f64>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = sources
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut work = BrandesWorkspace::new(n);
+                    let mut sum = vec![0.0; n];
+                    let mut sum_sq = vec![0.0; n];
+                    for &s in chunk {
+                        work.run(adj, s);
+                        for &w in &work.order {
+                            let x = work.delta[w] * scale;
+                            sum[w] += x;
+                            sum_sq[w] += x * x;
+                        }
+                    }
+                    (sum, sum_sq)
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    //merge the per-thread accumulators
+    let mut sum = vec![0.0; n];
+    let mut sum_sq = vec![0.0; n];
+    for (part_sum, part_sq) in partials {
+        for v in 0..n {
+            sum[v] += part_sum[v];
+            sum_sq[v] += part_sq[v];
         }
     }
+    (sum, sum_sq)
+}
 
-    //normalize values so the scale is consistent
-    let norm = if graph.node_count() <= 2 {
+//what raw betweenness sums are divided by, so the scale is consistent across graph sizes
+fn betweenness_norm(node_count: usize) -> f64 {
+    if node_count <= 2 {
         1.0
     } else {
-        ((graph.node_count() - 1) * (graph.node_count() - 2)) as f64 / 2.0
-    };
+        ((node_count - 1) * (node_count - 2)) as f64 / 2.0
+    }
+}
+
+//computes betweeness centrality for all nodes of the graph
+//input: reference to a graph
+//output: hashmap of the nodeidex to the centrality score
+pub fn betweenness_centrality<E>(
+    graph: &UnGraph<String, E>,
+) -> HashMap<NodeIndex, f64> {
+    let adj = adjacency(graph);
+    let sources: Vec<usize> = (0..adj.len()).collect();
+
+    //normalize values so the scale is consistent
+    let norm = betweenness_norm(graph.node_count());
+    let (bc, _) = accumulate_dependencies(&adj, &sources, 1.0 / norm);
+
+    graph.node_indices().map(|v| (v, bc[v.index()])).collect()
+}
+
+//z value for a two-sided 95% confidence interval
+const Z_95: f64 = 1.96;
+
+//how many source nodes approximate_betweenness samples
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PivotBudget {
+    //a fixed number of sources
+    Sources(usize),
+    //enough sources that, with probability at least 1 - delta, every score is within epsilon of the exact one
+    //(Hoeffding's bound with a union bound over all nodes)
+    ErrorBound { epsilon: f64, delta: f64 },
+}
 
-    for val in bc.values_mut() {
-        *val /= norm;
+impl PivotBudget {
+    //the number of sources to sample on a graph with node_count nodes (never more than node_count)
+    pub fn sources(&self, node_count: usize) -> usize {
+        let k = match *self {
+            PivotBudget::Sources(k) => k,
+            PivotBudget::ErrorBound { epsilon, delta } => {
+                //each sampled source contributes a value in [0, range] to a node's estimate
+                let n = node_count.max(3) as f64;
+                let range = 2.0 * n / (n - 1.0);
+                (range * range * (2.0 * n / delta).ln() / (2.0 * epsilon * epsilon)).ceil() as usize
+            }
+        };
+        k.clamp(1, node_count.max(1))
     }
+}
+
+//an approximate betweenness score with a 95% confidence interval around it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BetweennessEstimate {
+    pub score: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
 
-    bc
+//estimates betweenness centrality by running Brandes from a random sample of source nodes (pivots)
+//and scaling up, so it runs in bounded time on graphs far too big for betweenness_centrality
+//input: reference to a graph, how many pivots to use, and a random number generator (seed it to repeat a run)
+//output: hashmap of each node to its estimated score (on the same scale as betweenness_centrality) and a
+//95% confidence interval; if the budget covers every node the scores are exact and the intervals have zero width
+pub fn approximate_betweenness<E>(
+    graph: &UnGraph<String, E>,
+    budget: PivotBudget,
+    rng: &mut impl Rng,
+) -> HashMap<NodeIndex, BetweennessEstimate> {
+    let n = graph.node_count();
+    let k = budget.sources(n);
+    let pivots: Vec<NodeIndex> = if k >= n {
+        graph.node_indices().collect()
+    } else {
+        graph.node_indices().choose_multiple(rng, k)
+    };
+
+    //each pivot gives one unbiased sample of a node's score: n * delta / norm
+    let scale = n as f64 / betweenness_norm(n);
+    let adj = adjacency(graph);
+    let sources: Vec<usize> = pivots.iter().map(|p| p.index()).collect();
+    let (sum, sum_sq) = accumulate_dependencies(&adj, &sources, scale);
+
+    let k = pivots.len().max(1) as f64;
+    let exact = pivots.len() >= n;
+    graph
+        .node_indices()
+        .map(|v| {
+            let mean = sum[v.index()] / k;
+            let half_width = if exact || k < 2.0 {
+                0.0
+            } else {
+                let variance = ((sum_sq[v.index()] - k * mean * mean) / (k - 1.0)).max(0.0);
+                //sampling without replacement: shrink by the finite population correction
+                let fpc = ((n as f64 - k) / (n as f64 - 1.0)).max(0.0);
+                Z_95 * (variance / k * fpc).sqrt()
+            };
+            let estimate = BetweennessEstimate {
+                score: mean,
+                lower: (mean - half_width).max(0.0),
+                upper: mean + half_width,
+            };
+            (v, estimate)
+        })
+        .collect()
 }
 
 
@@ -293,6 +1859,16 @@ mod tests {
         data
     }
 
+    //helper function to turn a list of actor ids into a cast
+    fn cast(names: &[&str]) -> Vec<String> {
+        names.iter().map(|a| a.to_string()).collect()
+    }
+
+    //helper function to find an actor's node in a co-star graph
+    fn id<E>(graph: &UnGraph<String, E>, name: &str) -> NodeIndex {
+        graph.node_indices().find(|&n| graph[n] == name).unwrap()
+    }
+
     //test the building of the graph
     #[test]
     fn test_build_graph() {
@@ -302,6 +1878,129 @@ mod tests {
         assert_eq!(graph.edge_count(), 4); //expect 4 edges
     }
 
+    //test that each edge remembers the year of the earliest shared title with a known year
+    #[test]
+    fn test_build_graph_with_years() {
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("m1".to_string(), vec!["a1".to_string(), "a2".to_string()]);
+        movie_to_actors.insert("m2".to_string(), vec!["a1".to_string(), "a2".to_string(), "a3".to_string()]);
+        movie_to_actors.insert("m3".to_string(), vec!["a3".to_string(), "a4".to_string()]);
+        let title_years = TitleYears::from([("m1".to_string(), 2010), ("m2".to_string(), 2004)]);
+        let graph = build_graph_with_years(movie_to_actors, &title_years);
+        let year = |a: &str, b: &str| graph[graph.find_edge(id(&graph, a), id(&graph, b)).unwrap()].first_year;
+
+        assert_eq!(year("a1", "a2"), Some(2004));
+        assert_eq!(year("a2", "a3"), Some(2004));
+        assert_eq!(year("a3", "a4"), None); //m3 has no year
+    }
+
+    //test building a graph from only the titles that match a filter
+    #[test]
+    fn test_build_graph_filtered() {
+        let info = |title_type: &str, year: Option<u16>, genres: &[&str]| TitleInfo {
+            title_type: title_type.to_string(),
+            start_year: year,
+            runtime_minutes: None,
+            genres: genres.iter().map(|g| g.to_string()).collect(),
+        };
+        let titles = TitleTable::from([
+            ("m1".to_string(), info("movie", Some(1995), &["Action", "Drama"])),
+            ("m2".to_string(), info("tvEpisode", Some(2000), &["Action"])),
+            ("m3".to_string(), info("movie", Some(2015), &["Action"])),
+            ("m4".to_string(), info("tvMovie", Some(2005), &["Comedy"])),
+            ("m5".to_string(), info("movie", None, &["Action"])),
+        ]);
+        let mut movie_to_actors = sample_movie_to_actors(); //m1..m4
+        movie_to_actors.insert("m5".to_string(), vec!["a1".to_string(), "a3".to_string()]);
+        movie_to_actors.insert("m6".to_string(), vec!["a2".to_string(), "a4".to_string()]); //not in the table
+
+        let filter = TitleFilter {
+            title_types: vec!["movie".to_string(), "tvMovie".to_string()],
+            from_year: Some(1990),
+            to_year: Some(2010),
+            genres: vec![],
+        };
+        let graph = build_graph_filtered(movie_to_actors.clone(), &titles, &filter);
+        let mut kept: Vec<&str> = graph.edge_weights().flat_map(|e| e.titles.iter().map(|t| t.as_str())).collect();
+        kept.sort_unstable();
+        assert_eq!(kept, vec!["m1", "m4"]);
+        assert!(graph.edge_weights().all(|e| e.first_year.is_some()));
+
+        let action = TitleFilter { genres: vec!["action".to_string()], ..TitleFilter::default() };
+        assert_eq!(filter_titles(movie_to_actors.clone(), &titles, &action).len(), 4); //m1, m2, m3, m5
+        assert_eq!(filter_titles(movie_to_actors.clone(), &titles, &TitleFilter::default()).len(), 6);
+        assert_eq!(filter.describe(), "types=movie,tvMovie years=1990-2010");
+        assert_eq!(TitleFilter { from_year: Some(2000), ..action }.describe(), "years=2000- genres=action");
+    }
+
+    //test that casts over the limit are dropped or down-weighted, and the report of the biggest casts
+    #[test]
+    fn test_cast_limit() {
+        let cast = |n: usize| (0..n).map(|i| format!("a{}", i)).collect::<Vec<_>>();
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("film".to_string(), cast(3)); //a0, a1, a2
+        movie_to_actors.insert("talkshow".to_string(), cast(6));
+        let (years, billing) = (TitleYears::new(), TitleBilling::new());
+
+        let limited = |limit: CastLimit| CastOptions { limit: Some(limit), ..CastOptions::default() };
+        let drop = CastLimit { max_cast: 4, policy: BigCastPolicy::Drop };
+        let graph = build_graph_with_casts(movie_to_actors.clone(), &years, &billing, &limited(drop), |_| true);
+        assert_eq!(graph.edge_count(), 3);
+        assert_eq!(graph.node_count(), 6); //the talk show's actors are still in the graph, just not connected
+
+        let down = CastLimit { max_cast: 4, policy: BigCastPolicy::DownWeight };
+        let graph = build_graph_with_casts(movie_to_actors.clone(), &years, &billing, &limited(down), |_| true);
+        assert_eq!(graph.edge_count(), 15);
+        let (a0, a1, a5) = (id(&graph, "a0"), id(&graph, "a1"), id(&graph, "a5"));
+        let film_and_show = &graph[graph.find_edge(a0, a1).unwrap()];
+        assert_eq!((film_and_show.shared_titles, film_and_show.weight), (2, 1.6));
+        assert!((strength_centrality(&graph)[&a5] - 3.0).abs() < 1e-9); //capped at max_cast - 1
+
+        //a projected subset is judged by the full cast size
+        let subset = build_graph_with_casts(movie_to_actors.clone(), &years, &billing, &limited(drop), |a| a > "a2");
+        assert_eq!((subset.node_count(), subset.edge_count()), (3, 0));
+        assert_eq!(build_graph(movie_to_actors.clone()).edge_count(), 15);
+
+        let report = cast_report(movie_to_actors.iter().map(|(t, a)| (t.as_str(), a.len())), Some(&drop), 1);
+        assert_eq!(report.largest, vec![("talkshow".to_string(), 6, 15)]);
+        assert_eq!((report.total_edges, report.over_limit, report.edges_over_limit), (18, 1, 15));
+    }
+
+    //test connecting only the top billed actors, and weighting edges by billing
+    #[test]
+    fn test_billing() {
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("m1".to_string(), cast(&["lead", "second", "extra1", "extra2"])); //billing order
+        movie_to_actors.insert("m2".to_string(), cast(&["second", "lead"]));
+        let (years, billing) = (TitleYears::new(), TitleBilling::new());
+
+        let top_two = CastOptions { top_billed: Some(2), ..CastOptions::default() };
+        let graph = build_graph_with_casts(movie_to_actors.clone(), &years, &billing, &top_two, |_| true);
+        assert_eq!((graph.node_count(), graph.edge_count()), (4, 1));
+
+        let weighted = CastOptions { billing_weights: true, ..CastOptions::default() };
+        let graph = build_graph_with_casts(movie_to_actors.clone(), &years, &billing, &weighted, |_| true);
+        let weight = |a: &str, b: &str| graph[graph.find_edge(id(&graph, a), id(&graph, b)).unwrap()].weight;
+        assert!((weight("lead", "second") - (billing_weight(1, 2) + billing_weight(2, 1))).abs() < 1e-9);
+        assert!((weight("extra1", "extra2") - 1.0 / 12f64.sqrt()).abs() < 1e-9);
+        assert!(weight("lead", "extra1") > weight("second", "extra2"));
+        assert_eq!(billing_weight(1, 1), 1.0);
+        assert_eq!(billing_weight(1, 4), 0.5);
+
+        //top billing goes by IMDb's ordering, and credits without one are never in the top billed
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("m3".to_string(), cast(&["lead", "fourth", "unbilled"]));
+        let billing = TitleBilling::from([("m3".to_string(), vec![Some(1), Some(4), None])]);
+        assert_eq!(cast_billing("m3", 3, &billing), vec![(Some(1), 1), (Some(4), 4), (None, 5)]);
+        assert_eq!(cast_billing("m4", 2, &billing), vec![(Some(1), 1), (Some(2), 2)]); //in cast order
+        let graph = build_graph_with_casts(movie_to_actors.clone(), &years, &billing, &top_two, |_| true);
+        assert_eq!(graph.edge_count(), 0); //the second actor credited is billed 4th
+        let top_ten = CastOptions { top_billed: Some(10), billing_weights: true, ..CastOptions::default() };
+        let graph = build_graph_with_casts(movie_to_actors, &years, &billing, &top_ten, |_| true);
+        assert_eq!(graph.edge_count(), 1);
+        assert_eq!(graph.edge_weights().next().unwrap().weight, billing_weight(1, 4));
+    }
+
     //test the computation of degree centrality
     #[test]
     fn test_degree_centrality() {
@@ -319,6 +2018,26 @@ mod tests {
         }
     }
 
+    //test that repeat collaborations are counted on one edge, and that strength adds them up
+    #[test]
+    fn test_strength_centrality() {
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("m1".to_string(), vec!["a1".to_string(), "a2".to_string()]);
+        movie_to_actors.insert("m2".to_string(), vec!["a1".to_string(), "a2".to_string()]);
+        movie_to_actors.insert("m3".to_string(), vec!["a2".to_string(), "a1".to_string(), "a3".to_string()]);
+
+        let graph = build_graph(movie_to_actors);
+        assert_eq!(graph.edge_count(), 3);
+        let edge = graph.find_edge(id(&graph, "a1"), id(&graph, "a2")).unwrap();
+        assert_eq!(graph[edge].shared_titles, 3);
+        assert_eq!(graph[edge].titles, vec!["m1", "m2", "m3"]);
+
+        let strength = strength_centrality(&graph);
+        assert_eq!(strength[&id(&graph, "a1")], 4.0); //3 with a2, 1 with a3
+        assert_eq!(strength[&id(&graph, "a3")], 2.0);
+        assert_eq!(degree_centrality(&graph)[&id(&graph, "a1")], 2); //degree can't tell them apart
+    }
+
     //test the computation of closeness centrality
     #[test]
     fn test_closeness_centrality() {
@@ -331,6 +2050,266 @@ mod tests {
         }
     }
 
+    //test structural holes against values worked out by hand
+    #[test]
+    fn test_structural_holes() {
+        //b's contacts a, c, d: a and c know each other, d knows nobody else
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("m1".to_string(), cast(&["a", "b", "c"]));
+        movie_to_actors.insert("m2".to_string(), cast(&["b", "d"]));
+        movie_to_actors.insert("m3".to_string(), cast(&["e"]));
+        let graph = build_graph(movie_to_actors);
+
+        let b = ego_structural_holes(&graph, id(&graph, "b")).unwrap();
+        assert!((b.effective_size - (3.0 - 2.0 / 3.0)).abs() < 1e-9); //matches the unweighted formula
+        assert!((b.effective_size - ego_metrics(&graph, id(&graph, "b")).effective_size).abs() < 1e-9);
+        assert!((b.efficiency - 7.0 / 9.0).abs() < 1e-9);
+        //a and c: (1/3 + 1/3 * 1/2)^2 each, d: (1/3)^2
+        assert!((b.constraint - (2.0 * 0.25 + 1.0 / 9.0)).abs() < 1e-9);
+        assert!(b.hierarchy > 0.0 && b.hierarchy < 1.0);
+
+        //d has a single contact: fully constrained, no hierarchy
+        let d = ego_structural_holes(&graph, id(&graph, "d")).unwrap();
+        assert_eq!((d.effective_size, d.constraint, d.hierarchy), (1.0, 1.0, 0.0));
+        assert!(ego_structural_holes(&graph, id(&graph, "e")).is_none());
+
+        let all = structural_holes(&graph);
+        assert_eq!(all.len(), 4);
+        assert!(all[&id(&graph, "b")].constraint < all[&id(&graph, "a")].constraint); //b brokers between a/c and d
+
+        let ego = ego_network(&graph, &graph.node_indices().map(|n| (graph[n].clone(), n)).collect(), "b", 1).unwrap();
+        assert_eq!(ego.structural_holes(), Some(b));
+    }
+
+    //test triangle counts and clustering on two triangles sharing an edge, plus a pendant actor
+    #[test]
+    fn test_triangles_and_clustering() {
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("m1".to_string(), cast(&["a", "b", "c"]));
+        movie_to_actors.insert("m2".to_string(), cast(&["b", "c", "d"]));
+        movie_to_actors.insert("m3".to_string(), cast(&["d", "e"]));
+        let graph = build_graph(movie_to_actors);
+
+        let triangles = triangle_counts(&graph);
+        assert_eq!(triangles.values().sum::<usize>(), 6); //two triangles, three corners each
+        assert_eq!(triangles[&id(&graph, "b")], 2);
+        assert_eq!(triangles[&id(&graph, "e")], 0);
+
+        let local = clustering_coefficients(&graph);
+        assert_eq!(local[&id(&graph, "a")], 1.0);
+        assert!((local[&id(&graph, "b")] - 2.0 / 3.0).abs() < 1e-9);
+        assert!((local[&id(&graph, "d")] - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(local[&id(&graph, "e")], 0.0);
+        let average = (1.0 + 2.0 / 3.0 + 2.0 / 3.0 + 1.0 / 3.0) / 5.0;
+        assert!((average_clustering(&local) - average).abs() < 1e-9);
+        //6 closed out of a:1 + b:3 + c:3 + d:3 triples
+        assert!((transitivity(&graph, &triangles) - 0.6).abs() < 1e-9);
+    }
+
+    //test k-core decomposition on a 4-clique with a tail and an isolated actor
+    #[test]
+    fn test_core_numbers() {
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("m1".to_string(), cast(&["a", "b", "c", "d"]));
+        movie_to_actors.insert("m2".to_string(), cast(&["d", "e"]));
+        movie_to_actors.insert("m3".to_string(), cast(&["e", "f"]));
+        movie_to_actors.insert("m4".to_string(), cast(&["f", "d"])); //triangle d e f
+        movie_to_actors.insert("m5".to_string(), cast(&["f", "g"]));
+        movie_to_actors.insert("m6".to_string(), cast(&["h"]));
+        let graph = build_graph(movie_to_actors);
+
+        let core = core_numbers(&graph);
+        let expected = [("a", 3), ("b", 3), ("c", 3), ("d", 3), ("e", 2), ("f", 2), ("g", 1), ("h", 0)];
+        for (name, k) in expected {
+            assert_eq!(core[&id(&graph, name)], k, "{}", name);
+        }
+
+        let report = core_report(&core);
+        assert_eq!(report.max_core, 3);
+        assert_eq!(report.sizes, vec![8, 7, 6, 4]);
+        let mut innermost = vec![id(&graph, "a"), id(&graph, "b"), id(&graph, "c"), id(&graph, "d")];
+        innermost.sort_unstable();
+        assert_eq!(report.innermost, innermost);
+    }
+
+    //test that closeness penalises small components and that harmonic centrality matches by hand
+    #[test]
+    fn test_closeness_and_harmonic_disconnected() {
+        //a path a-b-c plus a separate pair d-e
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("m1".to_string(), vec!["a".to_string(), "b".to_string()]);
+        movie_to_actors.insert("m2".to_string(), vec!["b".to_string(), "c".to_string()]);
+        movie_to_actors.insert("m3".to_string(), vec!["d".to_string(), "e".to_string()]);
+        let graph = build_graph(movie_to_actors);
+
+        let closeness = closeness_centrality(&graph);
+        assert!((closeness[&id(&graph, "b")] - 0.5).abs() < 1e-9); //(2/4) * (2/2)
+        assert!((closeness[&id(&graph, "d")] - 0.25).abs() < 1e-9); //(1/4) * (1/1), not 1.0
+        assert!(closeness[&id(&graph, "b")] > closeness[&id(&graph, "d")]);
+
+        let harmonic = harmonic_centrality(&graph);
+        assert!((harmonic[&id(&graph, "a")] - 1.5 / 4.0).abs() < 1e-9); //1/1 + 1/2
+        assert!((harmonic[&id(&graph, "d")] - 0.25).abs() < 1e-9);
+    }
+
+    //test eigenvector and katz centrality on a star with one extra tie between two leaves
+    #[test]
+    fn test_eigenvector_and_katz_centrality() {
+        let mut movie_to_actors = HashMap::new();
+        for leaf in ["b", "c", "d", "e"] {
+            movie_to_actors.insert(format!("m_{}", leaf), vec!["a".to_string(), leaf.to_string()]);
+        }
+        movie_to_actors.insert("m_bc".to_string(), vec!["b".to_string(), "c".to_string()]);
+        let graph = build_graph(movie_to_actors);
+
+        let (scores, convergence) = eigenvector_centrality(&graph, 1e-10, 1000);
+        assert!(convergence.converged);
+        assert!((scores.values().map(|s| s * s).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(scores[&id(&graph, "a")] > scores[&id(&graph, "b")]);
+        assert!(scores[&id(&graph, "b")] > scores[&id(&graph, "d")]); //b's extra tie is to a well-connected actor
+        assert!((scores[&id(&graph, "b")] - scores[&id(&graph, "c")]).abs() < 1e-6);
+
+        let (scores, convergence) = katz_centrality(&graph, 0.1, 1e-10, 1000);
+        assert!(convergence.converged);
+        assert!(scores[&id(&graph, "a")] > scores[&id(&graph, "b")]);
+        assert!(scores[&id(&graph, "b")] > scores[&id(&graph, "d")]);
+
+        let (_, convergence) = katz_centrality(&graph, 2.0, 1e-10, 50); //alpha too large
+        assert!(!convergence.converged);
+        assert_eq!(convergence.iterations, 50);
+    }
+
+    //test that pagerank sums to one, favours hubs, and follows heavier edges
+    #[test]
+    fn test_pagerank() {
+        let graph = build_graph(sample_movie_to_actors());
+        let scores = pagerank(&graph, &PageRankOptions::default());
+        assert!((scores.values().sum::<f64>() - 1.0).abs() < 1e-9);
+        for score in scores.values() {
+            assert!((score - 0.25).abs() < 1e-6); //every actor of the 4-cycle is alike
+        }
+
+        //a1 shares two titles with a2 and one with a3
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("m1".to_string(), vec!["a1".to_string(), "a2".to_string()]);
+        movie_to_actors.insert("m2".to_string(), vec!["a1".to_string(), "a2".to_string()]);
+        movie_to_actors.insert("m3".to_string(), vec!["a1".to_string(), "a3".to_string()]);
+        movie_to_actors.insert("m4".to_string(), vec!["a4".to_string(), "a5".to_string()]);
+        let graph = build_graph(movie_to_actors);
+        let scores = pagerank(&graph, &PageRankOptions::default());
+        assert!(scores[&id(&graph, "a1")] > scores[&id(&graph, "a2")]);
+        assert!(scores[&id(&graph, "a2")] > scores[&id(&graph, "a3")]);
+
+        let related = personalized_pagerank(&graph, &[id(&graph, "a1")], &PageRankOptions::default());
+        assert!((related.values().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(related[&id(&graph, "a2")] > related[&id(&graph, "a3")]);
+        assert_eq!(related[&id(&graph, "a4")], 0.0); //not reachable from the seed
+        assert!(personalized_pagerank(&graph, &[], &PageRankOptions::default()).values().all(|&s| s == 0.0));
+    }
+
+    //test that louvain splits two cliques joined by one edge, and that resolution changes the split
+    #[test]
+    fn test_louvain_communities() {
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("m1".to_string(), cast(&["a1", "a2", "a3", "a4"]));
+        movie_to_actors.insert("m2".to_string(), cast(&["b1", "b2", "b3", "b4"]));
+        movie_to_actors.insert("m3".to_string(), cast(&["a1", "b1"]));
+        movie_to_actors.insert("m4".to_string(), cast(&["c1"])); //an isolated actor
+        let graph = build_graph(movie_to_actors);
+
+        let communities = louvain_communities(&graph, 1.0);
+        assert_eq!(communities.count, 3);
+        let of = |name: &str| communities.community[&id(&graph, name)];
+        assert_eq!(of("a2"), of("a1"));
+        assert_eq!(of("a4"), of("a3"));
+        assert_eq!(of("a1"), of("a3"));
+        assert_eq!(of("b1"), of("b4"));
+        assert_ne!(of("a1"), of("b1"));
+        //13 edges: each clique has 6 inside, so Q = 2 * (12/26 - (13/26)^2)
+        let expected = 2.0 * (12.0 / 26.0 - (13.0f64 / 26.0).powi(2));
+        assert!((communities.modularity - expected).abs() < 1e-9);
+        assert!((modularity(&graph, &communities.community, 1.0) - expected).abs() < 1e-9);
+        assert_eq!(communities.members().iter().map(|m| m.len()).sum::<usize>(), 9);
+
+        //a very low resolution favours one community per component
+        let coarse = louvain_communities(&graph, 0.01);
+        assert_eq!(coarse.count, 2);
+        //a very high resolution leaves every actor on their own
+        assert_eq!(louvain_communities(&graph, 100.0).count, 9);
+    }
+
+    //test that label propagation finds two cliques joined by one edge, and is repeatable with a seed
+    #[test]
+    fn test_label_propagation() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("m1".to_string(), cast(&["a1", "a2", "a3", "a4", "a5"]));
+        movie_to_actors.insert("m2".to_string(), cast(&["b1", "b2", "b3", "b4", "b5"]));
+        movie_to_actors.insert("m3".to_string(), cast(&["a1", "b1"]));
+        movie_to_actors.insert("m4".to_string(), cast(&["c1"]));
+        let graph = build_graph(movie_to_actors);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let communities = label_propagation(&graph, &mut rng);
+        let mut sizes: Vec<usize> = communities.values().map(|c| c.len()).collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![1, 5, 5]);
+        for community in communities.values() {
+            let first = graph[community[0]].chars().next();
+            assert!(community.iter().all(|&n| graph[n].chars().next() == first));
+        }
+
+        let sorted = |seed: u64| {
+            let mut groups: Vec<Vec<NodeIndex>> =
+                label_propagation(&graph, &mut StdRng::seed_from_u64(seed)).into_values().collect();
+            groups.sort();
+            groups
+        };
+        assert_eq!(sorted(11), sorted(11));
+    }
+
+    //test articulation points and bridges on two triangles joined through a middle actor, plus a tail
+    #[test]
+    fn test_articulation_points_and_bridges() {
+        //triangle a1 a2 a3, a3 - m, m - b1, triangle b1 b2 b3, b3 - t
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("m1".to_string(), cast(&["a1", "a2", "a3"]));
+        movie_to_actors.insert("m2".to_string(), cast(&["a3", "m"]));
+        movie_to_actors.insert("m3".to_string(), cast(&["m", "b1"]));
+        movie_to_actors.insert("m4".to_string(), cast(&["b1", "b2", "b3"]));
+        movie_to_actors.insert("m5".to_string(), cast(&["b3", "t"]));
+        movie_to_actors.insert("m6".to_string(), cast(&["x", "y"]));
+        let graph = build_graph(movie_to_actors);
+
+        let points = articulation_points(&graph);
+        let names: HashSet<&str> = points.iter().map(|p| graph[p.node].as_str()).collect();
+        assert_eq!(names, HashSet::from(["a3", "m", "b1", "b3"]));
+        let m = points.iter().find(|p| p.node == id(&graph, "m")).unwrap();
+        assert_eq!(m.pieces, vec![
+            CutPiece { size: 4, neighbours: vec![id(&graph, "b1")] },
+            CutPiece { size: 3, neighbours: vec![id(&graph, "a3")] },
+        ]);
+        assert_eq!(points[0].node, id(&graph, "m")); //cuts off the most actors
+        let b3 = points.iter().find(|p| p.node == id(&graph, "b3")).unwrap();
+        assert_eq!(b3.split_off(), 1);
+
+        let found = bridges(&graph);
+        assert_eq!(found.len(), 4); //a3-m, m-b1, b3-t, x-y
+        let sides = |a: &str, b: &str| {
+            let bridge = found
+                .iter()
+                .find(|br| br.ends == (id(&graph, a), id(&graph, b)) || br.ends == (id(&graph, b), id(&graph, a)))
+                .unwrap();
+            if bridge.ends.0 == id(&graph, a) { bridge.sizes } else { (bridge.sizes.1, bridge.sizes.0) }
+        };
+        assert_eq!(sides("m", "b1"), (4, 4));
+        assert_eq!(sides("a3", "m"), (3, 5));
+        assert_eq!(sides("b3", "t"), (7, 1));
+        assert_eq!(sides("x", "y"), (1, 1));
+        assert_eq!(found[0].sizes, (4, 4));
+    }
+
     //test number of connected components
     #[test]
     fn test_connected_components() {
@@ -360,6 +2339,37 @@ mod tests {
         assert_eq!(len, Some(2)); //path is a1->a2->a3
     }
 
+    //test reconstructing the path and the titles that link each hop
+    #[test]
+    fn test_shortest_path() {
+        let graph = build_graph(sample_movie_to_actors());
+        let actor_id_map: HashMap<String, NodeIndex> =
+            graph.node_references().map(|(idx, id)| (id.clone(), idx)).collect();
+
+        let path = shortest_path(&graph, &actor_id_map, "a1", "a3").unwrap();
+        assert_eq!(path.len(), 2);
+        assert_eq!(graph[path[0].from], "a1");
+        assert_eq!(path[0].to, path[1].from);
+        assert_eq!(graph[path[1].to], "a3");
+        //a1 reaches a3 either through a2 (m1, m2) or through a4 (m4, m3)
+        let titles: Vec<_> = path.iter().map(|s| s.titles[0].as_str()).collect();
+        assert!(titles == ["m1", "m2"] || titles == ["m4", "m3"]);
+
+        let names: HashMap<String, String> =
+            [("a1", "Ann"), ("a2", "Bob"), ("a3", "Cat"), ("a4", "Dan")]
+                .iter()
+                .map(|(id, n)| (id.to_string(), n.to_string()))
+                .collect();
+        let text = describe_path(&graph, &path, &names);
+        assert!(
+            text == "Ann was in m1 with Bob, who was in m2 with Cat"
+                || text == "Ann was in m4 with Dan, who was in m3 with Cat"
+        );
+
+        assert_eq!(shortest_path(&graph, &actor_id_map, "a1", "a1"), Some(vec![]));
+        assert_eq!(shortest_path(&graph, &actor_id_map, "a1", "nobody"), None);
+    }
+
     //test extracting a subgraph within one "step" of node C
     #[test]
     fn test_extract_subgraph_around_actor() {
@@ -389,6 +2399,103 @@ mod tests {
         assert_eq!(names, expected);
     }
 
+    //test that the ego network keeps edges between the ego's neighbours, and its metrics
+    #[test]
+    fn test_ego_network() {
+        //ego E with alters A, B (who worked together) and C (who didn't), plus D two steps away via C
+        let mut graph = UnGraph::<String, ()>::new_undirected();
+        let ids = ["E", "A", "B", "C", "D"];
+        let nodes: Vec<_> = ids.iter().map(|id| graph.add_node(id.to_string())).collect();
+        graph.add_edge(nodes[0], nodes[1], ());
+        graph.add_edge(nodes[0], nodes[2], ());
+        graph.add_edge(nodes[0], nodes[3], ());
+        graph.add_edge(nodes[1], nodes[2], ());
+        graph.add_edge(nodes[3], nodes[4], ());
+        let actor_id_map: HashMap<String, NodeIndex> =
+            ids.iter().zip(&nodes).map(|(id, &n)| (id.to_string(), n)).collect();
+
+        let ego = ego_network(&graph, &actor_id_map, "E", 1).unwrap();
+        assert_eq!(ego.graph.node_count(), 4);
+        assert_eq!(ego.graph.edge_count(), 4); //3 spokes plus A-B
+        assert_eq!(ego.graph[ego.ego], "E");
+        assert_eq!(ego.metrics.alters, 3);
+        assert_eq!(ego.metrics.alter_ties, 1);
+        assert!((ego.metrics.density - 1.0 / 3.0).abs() < 1e-9);
+        assert!((ego.metrics.effective_size - (3.0 - 2.0 / 3.0)).abs() < 1e-9);
+        assert_eq!(ego.metrics.alter_components, 2); //{A, B} and {C}
+
+        let wider = ego_network(&graph, &actor_id_map, "E", 2).unwrap();
+        assert_eq!(wider.graph.edge_count(), 5);
+        assert_eq!(wider.metrics.alter_components, 2); //{A, B} and {C, D}
+        assert!(ego_network(&graph, &actor_id_map, "Z", 1).is_none());
+    }
+
+    //test betweenness on a star, and on a graph whose path counts overflow a usize
+    #[test]
+    fn test_betweenness_centrality() {
+        //star: the centre lies between every pair of leaves
+        let mut star = UnGraph::<String, ()>::new_undirected();
+        let centre = star.add_node("c".to_string());
+        for i in 0..4 {
+            let leaf = star.add_node(format!("l{}", i));
+            star.add_edge(centre, leaf, ());
+        }
+        let bc = betweenness_centrality(&star);
+        assert!((bc[&centre] - 2.0).abs() < 1e-9); //12 ordered leaf pairs / (4 * 3 / 2)
+        assert_eq!(bc[&NodeIndex::new(1)], 0.0);
+
+        //70 layers of 4 nodes, each fully joined to the next: 4^69 shortest paths end to end
+        let mut layered = UnGraph::<String, ()>::new_undirected();
+        let layers: Vec<Vec<NodeIndex>> = (0..70)
+            .map(|l| (0..4).map(|i| layered.add_node(format!("{}-{}", l, i))).collect())
+            .collect();
+        for pair in layers.windows(2) {
+            for &a in &pair[0] {
+                for &b in &pair[1] {
+                    layered.add_edge(a, b, ());
+                }
+            }
+        }
+        let bc = betweenness_centrality(&layered);
+        assert!(bc.values().all(|b| b.is_finite()));
+        //the middle of the chain is more between than its ends
+        assert!(bc[&layers[35][0]] > bc[&layers[1][0]]);
+        assert!((bc[&layers[35][0]] - bc[&layers[35][3]]).abs() < 1e-9);
+    }
+
+    //test that sampling every node gives exact betweenness, and fewer pivots give a sensible interval
+    #[test]
+    fn test_approximate_betweenness() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        //a path a-b-c-d-e: b, c and d lie between others, the ends don't
+        let mut graph = UnGraph::<String, ()>::new_undirected();
+        let nodes: Vec<_> = (0..5).map(|i| graph.add_node(format!("a{}", i))).collect();
+        for pair in nodes.windows(2) {
+            graph.add_edge(pair[0], pair[1], ());
+        }
+        let exact = betweenness_centrality(&graph);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let full = approximate_betweenness(&graph, PivotBudget::Sources(100), &mut rng);
+        for v in graph.node_indices() {
+            assert!((full[&v].score - exact[&v]).abs() < 1e-9);
+            assert_eq!(full[&v].lower, full[&v].upper);
+        }
+
+        let approx = approximate_betweenness(&graph, PivotBudget::Sources(3), &mut rng);
+        assert_eq!(approx[&nodes[0]].score, 0.0); //an end is never between anyone
+        for v in graph.node_indices() {
+            assert!(approx[&v].lower <= approx[&v].score && approx[&v].score <= approx[&v].upper);
+        }
+
+        //tighter error bounds need more pivots
+        let loose = PivotBudget::ErrorBound { epsilon: 0.5, delta: 0.1 }.sources(1_000_000);
+        let tight = PivotBudget::ErrorBound { epsilon: 0.05, delta: 0.1 }.sources(1_000_000);
+        assert!(loose < tight && tight < 1_000_000);
+    }
+
     //test generation of a random subgraph
     #[test]
     fn test_random_actor_subgraph() {
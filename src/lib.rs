@@ -0,0 +1,9 @@
+//the analysis library; main.rs is a thin program on top of it
+pub mod backtest;
+pub mod bipartite;
+pub mod cli;
+pub mod graph;
+pub mod link_prediction;
+pub mod names;
+pub mod parser;
+pub mod snapshot;
@@ -0,0 +1,274 @@
+//scores pairs of actors who have not worked together by how likely they are to, from the shape of the graph
+//around them: who they both know, and how well connected they are
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use petgraph::graph::{NodeIndex, UnGraph};
+
+use crate::bipartite::ActorTitleGraph;
+use crate::graph::CastOptions;
+
+//the link prediction scores; higher always means more likely
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkScore {
+    //number of shared co-stars
+    CommonNeighbours,
+    //shared co-stars as a share of everyone either actor has worked with
+    Jaccard,
+    //shared co-stars, each counting 1 / ln(their number of co-stars), so hubs count less
+    AdamicAdar,
+    //shared co-stars, each counting 1 / (their number of co-stars)
+    ResourceAllocation,
+    //product of the two actors' numbers of co-stars
+    PreferentialAttachment,
+}
+
+impl LinkScore {
+    pub fn all() -> [LinkScore; 5] {
+        [
+            LinkScore::CommonNeighbours,
+            LinkScore::Jaccard,
+            LinkScore::AdamicAdar,
+            LinkScore::ResourceAllocation,
+            LinkScore::PreferentialAttachment,
+        ]
+    }
+
+    //the name used on the command line
+    pub fn parse(s: &str) -> Option<LinkScore> {
+        match s {
+            "common-neighbours" => Some(LinkScore::CommonNeighbours),
+            "jaccard" => Some(LinkScore::Jaccard),
+            "adamic-adar" => Some(LinkScore::AdamicAdar),
+            "resource-allocation" => Some(LinkScore::ResourceAllocation),
+            "preferential-attachment" => Some(LinkScore::PreferentialAttachment),
+            _ => None,
+        }
+    }
+
+    //whether only pairs with a shared co-star can score above 0
+    fn needs_common_neighbour(self) -> bool {
+        self != LinkScore::PreferentialAttachment
+    }
+}
+
+impl fmt::Display for LinkScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LinkScore::CommonNeighbours => "Common Neighbours",
+            LinkScore::Jaccard => "Jaccard",
+            LinkScore::AdamicAdar => "Adamic-Adar",
+            LinkScore::ResourceAllocation => "Resource Allocation",
+            LinkScore::PreferentialAttachment => "Preferential Attachment",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+fn neighbours<E>(graph: &UnGraph<String, E>, node: NodeIndex) -> HashSet<NodeIndex> {
+    graph.neighbors(node).filter(|&w| w != node).collect()
+}
+
+fn degree<E>(graph: &UnGraph<String, E>, node: NodeIndex) -> usize {
+    graph.neighbors(node).filter(|&w| w != node).count()
+}
+
+//what one shared co-star with this many co-stars adds to a score
+fn shared_weight(score: LinkScore, degree: usize) -> f64 {
+    match score {
+        //a shared co-star has at least two co-stars, so ln is positive
+        LinkScore::AdamicAdar => 1.0 / (degree as f64).ln(),
+        LinkScore::ResourceAllocation => 1.0 / degree as f64,
+        _ => 1.0,
+    }
+}
+
+//turns the sum over shared co-stars into the score
+fn finish(score: LinkScore, shared: f64, common: usize, degree_a: usize, degree_b: usize) -> f64 {
+    match score {
+        LinkScore::Jaccard => {
+            let union = degree_a + degree_b - common;
+            if union == 0 { 0.0 } else { common as f64 / union as f64 }
+        }
+        LinkScore::PreferentialAttachment => (degree_a * degree_b) as f64,
+        _ => shared,
+    }
+}
+
+//scores one pair of actors
+//input: a reference to the graph, the two nodes and the score to compute
+//output: the score (the pair may already be adjacent; callers that only want new pairs should check)
+pub fn score_pair<E>(graph: &UnGraph<String, E>, a: NodeIndex, b: NodeIndex, score: LinkScore) -> f64 {
+    let (na, nb) = (neighbours(graph, a), neighbours(graph, b));
+    let (small, large) = if na.len() <= nb.len() { (&na, &nb) } else { (&nb, &na) };
+    let mut shared = 0.0;
+    let mut common = 0;
+    for z in small.iter().filter(|z| large.contains(z)) {
+        common += 1;
+        shared += shared_weight(score, degree(graph, *z));
+    }
+    finish(score, shared, common, na.len(), nb.len())
+}
+
+//finds the actors an actor is most likely to work with next
+//input: a reference to the graph, the actor id to node map, the actor id, the score and how many to return
+//output: up to k actors the actor has not worked with, best first with their scores
+//(None if the actor isn't in the graph)
+pub fn likely_collaborators<E>(
+    graph: &UnGraph<String, E>,
+    actor_id_map: &HashMap<String, NodeIndex>,
+    actor_id: &str,
+    score: LinkScore,
+    k: usize,
+) -> Option<Vec<(NodeIndex, f64)>> {
+    let a = *actor_id_map.get(actor_id)?;
+    let own = neighbours(graph, a);
+
+    //walk two steps out, summing what each shared co-star adds
+    let mut shared: HashMap<NodeIndex, (f64, usize)> = HashMap::new();
+    for &z in &own {
+        let weight = shared_weight(score, degree(graph, z));
+        for c in graph.neighbors(z) {
+            if c != a && c != z && !own.contains(&c) {
+                let entry = shared.entry(c).or_default();
+                entry.0 += weight;
+                entry.1 += 1;
+            }
+        }
+    }
+    //preferential attachment also scores actors with no co-star in common (everyone else in the graph)
+    if !score.needs_common_neighbour() {
+        for c in graph.node_indices() {
+            if c != a && !own.contains(&c) {
+                shared.entry(c).or_default();
+            }
+        }
+    }
+
+    let mut ranked: Vec<(NodeIndex, f64)> = shared
+        .into_iter()
+        .map(|(c, (sum, common))| (c, finish(score, sum, common, own.len(), degree(graph, c))))
+        .collect();
+    ranked.sort_by(|x, y| y.1.partial_cmp(&x.1).unwrap().then(x.0.cmp(&y.0)));
+    ranked.truncate(k);
+    Some(ranked)
+}
+
+//finds the actors within `depth` hops of an actor that they are most likely to work with next, without
+//projecting the whole graph
+//the ego network is projected one hop further than the candidates reach, so every candidate and every shared
+//co-star keeps all of its co-stars and the scores come out as on the full graph; only the candidates are
+//limited to `depth` hops (so preferential attachment does not rank actors further away)
+//input: the bipartite graph, the actor id, the depth, the score, how many to return and the cast options
+//output: the ids of up to k candidates, best first with their scores
+//(None if the actor isn't in the graph)
+pub fn likely_collaborators_within(
+    actor_titles: &ActorTitleGraph,
+    actor_id: &str,
+    depth: usize,
+    score: LinkScore,
+    k: usize,
+    casts: &CastOptions,
+) -> Option<Vec<(String, f64)>> {
+    let candidates: HashSet<&str> =
+        actor_titles.ego_actors(actor_id, depth).into_iter().map(|n| actor_titles.id(n)).collect();
+    let graph = actor_titles.project_actors_with(&actor_titles.ego_actors(actor_id, depth + 1), casts);
+    let actor_id_map: HashMap<String, NodeIndex> = graph.node_indices().map(|n| (graph[n].clone(), n)).collect();
+    let mut ranked = likely_collaborators(&graph, &actor_id_map, actor_id, score, graph.node_count())?;
+    ranked.retain(|&(c, _)| candidates.contains(graph[c].as_str()));
+    ranked.truncate(k);
+    Some(ranked.into_iter().map(|(c, s)| (graph[c].clone(), s)).collect())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::build_graph;
+
+    //helper function to turn a list of actor ids into a cast
+    fn cast(names: &[&str]) -> Vec<String> {
+        names.iter().map(|a| a.to_string()).collect()
+    }
+
+    //helper function to find an actor's node in a co-star graph
+    fn id<E>(graph: &UnGraph<String, E>, name: &str) -> NodeIndex {
+        graph.node_indices().find(|&n| graph[n] == name).unwrap()
+    }
+
+    //a and b share c and d; d also knows e; f is on its own with g
+    fn sample_graph() -> UnGraph<String, crate::graph::CoStar> {
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("m1".to_string(), cast(&["a", "c"]));
+        movie_to_actors.insert("m2".to_string(), cast(&["a", "d"]));
+        movie_to_actors.insert("m3".to_string(), cast(&["b", "c"]));
+        movie_to_actors.insert("m4".to_string(), cast(&["b", "d"]));
+        movie_to_actors.insert("m5".to_string(), cast(&["d", "e"]));
+        movie_to_actors.insert("m6".to_string(), cast(&["f", "g"]));
+        build_graph(movie_to_actors)
+    }
+
+    //test each score on one pair against values worked out by hand
+    #[test]
+    fn test_score_pair() {
+        let graph = sample_graph();
+        let (a, b) = (id(&graph, "a"), id(&graph, "b"));
+        assert_eq!(score_pair(&graph, a, b, LinkScore::CommonNeighbours), 2.0);
+        assert_eq!(score_pair(&graph, a, b, LinkScore::Jaccard), 1.0);
+        let adamic_adar = 1.0 / 2f64.ln() + 1.0 / 3f64.ln();
+        assert!((score_pair(&graph, a, b, LinkScore::AdamicAdar) - adamic_adar).abs() < 1e-9);
+        assert!((score_pair(&graph, a, b, LinkScore::ResourceAllocation) - (0.5 + 1.0 / 3.0)).abs() < 1e-9);
+        assert_eq!(score_pair(&graph, a, b, LinkScore::PreferentialAttachment), 4.0);
+        assert_eq!(score_pair(&graph, a, id(&graph, "f"), LinkScore::CommonNeighbours), 0.0);
+    }
+
+    //test the top-k query agrees with score_pair and skips existing co-stars
+    #[test]
+    fn test_likely_collaborators() {
+        let graph = sample_graph();
+        let actor_id_map: HashMap<String, NodeIndex> = graph.node_indices().map(|n| (graph[n].clone(), n)).collect();
+        let id = |name: &str| actor_id_map[name];
+
+        for score in LinkScore::all() {
+            let top = likely_collaborators(&graph, &actor_id_map, "a", score, 10).unwrap();
+            assert!(top.iter().all(|&(c, _)| c != id("a") && c != id("c") && c != id("d")));
+            for &(c, s) in &top {
+                assert!((s - score_pair(&graph, id("a"), c, score)).abs() < 1e-9, "{}", score);
+            }
+        }
+        let top = likely_collaborators(&graph, &actor_id_map, "a", LinkScore::AdamicAdar, 10).unwrap();
+        assert_eq!(top.iter().map(|&(c, _)| c).collect::<Vec<_>>(), vec![id("b"), id("e")]);
+        //preferential attachment also considers actors two components away
+        let top = likely_collaborators(&graph, &actor_id_map, "a", LinkScore::PreferentialAttachment, 10).unwrap();
+        assert_eq!(top.len(), 4);
+        assert_eq!(top[0], (id("b"), 4.0));
+        assert_eq!(likely_collaborators(&graph, &actor_id_map, "a", LinkScore::Jaccard, 1).unwrap().len(), 1);
+        assert!(likely_collaborators(&graph, &actor_id_map, "zz", LinkScore::Jaccard, 1).is_none());
+    }
+
+    //test that a candidate at the edge of the ego network is scored with all of its co-stars
+    #[test]
+    fn test_likely_collaborators_within() {
+        //d is 2 hops from a, and has co-stars e and f 3 hops out
+        let mut movie_to_actors = HashMap::new();
+        movie_to_actors.insert("m1".to_string(), cast(&["a", "b"]));
+        movie_to_actors.insert("m2".to_string(), cast(&["b", "d"]));
+        movie_to_actors.insert("m3".to_string(), cast(&["d", "e"]));
+        movie_to_actors.insert("m4".to_string(), cast(&["d", "f"]));
+        let actor_titles = ActorTitleGraph::build(&movie_to_actors);
+        let full = build_graph(movie_to_actors);
+
+        for score in LinkScore::all() {
+            let ranked =
+                likely_collaborators_within(&actor_titles, "a", 2, score, 10, &CastOptions::default()).unwrap();
+            let names: Vec<&str> = ranked.iter().map(|(c, _)| c.as_str()).collect();
+            assert_eq!(names, vec!["d"], "{}", score); //e and f are beyond 2 hops
+            assert!((ranked[0].1 - score_pair(&full, id(&full, "a"), id(&full, "d"), score)).abs() < 1e-9, "{}", score);
+        }
+        let ranked =
+            likely_collaborators_within(&actor_titles, "a", 2, LinkScore::Jaccard, 10, &CastOptions::default()).unwrap();
+        assert!((ranked[0].1 - 1.0 / 3.0).abs() < 1e-9); //b out of b, e and f, not b out of b
+        assert!(likely_collaborators_within(&actor_titles, "zz", 2, LinkScore::Jaccard, 10, &CastOptions::default())
+            .is_none());
+    }
+}
@@ -1,111 +1,797 @@
 use std::collections::HashMap;
-use petgraph::graph::NodeIndex;
-use petgraph::visit::IntoNodeReferences;
-use rand::thread_rng;
+use std::path::Path;
+use petgraph::graph::{NodeIndex, UnGraph};
+use rand::rngs::StdRng;
+use rand::{thread_rng, SeedableRng};
 
-mod graph;
-mod parser;
-
-use graph::{
-    build_graph, degree_centrality, closeness_centrality, betweenness_centrality,
-    extract_subgraph_around_actor, shortest_path_length, random_actor_subgraph
+use final_project::backtest::{evaluate_scores, split_at_year, BacktestOptions};
+use final_project::bipartite::ActorTitleGraph;
+use final_project::cli::{parse_args, Command, CommunityMethod, Measure, Options, Target, USAGE};
+use final_project::link_prediction::{likely_collaborators_within, LinkScore};
+use final_project::names::{Candidate, NameIndex};
+use final_project::graph::{
+    degree_centrality, closeness_centrality, harmonic_centrality, betweenness_centrality, strength_centrality, ego_metrics,
+    approximate_betweenness, articulation_points, average_clustering, bridges, clustering_coefficients,
+    build_graph_with_casts, cast_report, core_numbers, filter_titles, core_report, transitivity, triangle_counts, ego_structural_holes, eigenvector_centrality,
+    structural_holes, label_propagation, louvain_communities, modularity, katz_centrality, pagerank, personalized_pagerank, CoStar, Convergence, PageRankOptions, PivotBudget
+};
+use final_project::parser::{
+    read_dataset, read_imdb_dumps, read_title_basics, title_years, ActorNames, MovieToActors, ParseError, ParseMode,
+    TitleBilling, TitleTable, TitleYears,
 };
-use parser::read_dataset;
+use final_project::snapshot::{dataset_version, load_or_build, SnapshotHeader};
 
-fn main() {
-    // read dataset
-    let (movie_to_actors, actor_id_to_name) = read_dataset("actor_name_data.tsv");
+// when eigenvector and katz centrality stop iterating
+const TOLERANCE: f64 = 1e-9;
+const MAX_ITERATIONS: usize = 1000;
+
+// read dataset (the cleaned tsv if it is there, otherwise straight from the IMDb dumps)
+// malformed rows in the cleaned tsv are skipped and written to --quarantine instead of ending the run
+// with --titles, title.basics is joined on and only the titles passing --type/--years/--genre are kept
+// output: the credits, the actor names, every known title year, the ordering of each credit, and whether the
+// casts are in billing order (title.principals always says; the cleaned tsv only with an ordering column)
+fn read_credits(
+    options: &Options,
+) -> Result<(MovieToActors, ActorNames, TitleYears, TitleBilling, bool), ParseError> {
+    let (movie_to_actors, actor_id_to_name, mut years, billing, billed) = if Path::new(&options.data).exists() {
+        let mode = ParseMode::Lenient { quarantine_path: options.quarantine.clone() };
+        let (movie_to_actors, actor_id_to_name, years, billing, stats) = read_dataset(&options.data, &mode)?;
+        if stats.rows_skipped > 0 {
+            match &options.quarantine {
+                Some(path) => println!("Skipped {} of {} rows, see {}.", stats.rows_skipped, stats.rows_read, path),
+                None => println!("Skipped {} of {} rows.", stats.rows_skipped, stats.rows_read),
+            }
+        }
+        let billed = stats.rows_billed > 0 && stats.rows_billed == stats.rows_read - stats.rows_skipped;
+        (movie_to_actors, actor_id_to_name, years, billing, billed)
+    } else {
+        let (movie_to_actors, actor_id_to_name, billing, stats) =
+            read_imdb_dumps(&options.principals, &options.names)?;
+        if stats.rows_skipped > 0 {
+            println!(
+                "Skipped {} of {} rows of {} and {}.",
+                stats.rows_skipped, stats.rows_read, options.principals, options.names
+            );
+        }
+        (movie_to_actors, actor_id_to_name, TitleYears::new(), billing, true)
+    };
+    let movie_to_actors = match &options.titles {
+        Some(path) => {
+            let titles = read_titles(path, |t| movie_to_actors.contains_key(t))?;
+            years.extend(title_years(&titles));
+            filter_titles(movie_to_actors, &titles, &options.filter)
+        }
+        None => movie_to_actors,
+    };
+    Ok((movie_to_actors, actor_id_to_name, years, billing, billed))
+}
+
+// --top-billed and --billing-weights only make sense when the casts are in billing order
+fn check_billing(billed: bool, options: &Options) -> Result<(), String> {
+    if !billed && (options.casts.top_billed.is_some() || options.casts.billing_weights) {
+        return Err(format!(
+            "--top-billed and --billing-weights need to know how credits were billed, but {} does not give \
+             every credit an ordering; add that column or read the IMDb dumps instead",
+            options.data
+        ));
+    }
+    Ok(())
+}
+
+// reads title.basics for the titles that pass `keep`, saying how many malformed rows were skipped
+fn read_titles(path: &str, keep: impl Fn(&str) -> bool) -> Result<TitleTable, ParseError> {
+    let (titles, stats) = read_title_basics(path, keep)?;
+    if stats.rows_skipped > 0 {
+        println!("Skipped {} of {} rows of {}.", stats.rows_skipped, stats.rows_read, path);
+    }
+    Ok(titles)
+}
+
+// build the bipartite actor-title graph; co-star graphs are only projected for the subsets we analyse
+fn build_from_dataset(options: &Options) -> Result<(ActorTitleGraph, ActorNames), ParseError> {
+    let (movie_to_actors, actor_id_to_name, _, billing, billed) = read_credits(options)?;
+    if !options.filter.is_empty() {
+        println!("Keeping the {} titles matching {}.", movie_to_actors.len(), options.filter.describe());
+    }
+    let mut actor_titles = ActorTitleGraph::build_billed(&movie_to_actors, &billing);
+    actor_titles.billed = billed;
+    Ok((actor_titles, actor_id_to_name))
+}
+
+// reuse the snapshot from an earlier run unless the input files or the title filter have changed since
+fn load_graph(options: &Options) -> Result<(ActorTitleGraph, ActorNames), String> {
+    let mut inputs = if Path::new(&options.data).exists() {
+        vec![options.data.as_str()]
+    } else {
+        vec![options.principals.as_str(), options.names.as_str()]
+    };
+    if let (Some(titles), false) = (&options.titles, options.filter.is_empty()) {
+        inputs.push(titles.as_str());
+    }
+    let version = dataset_version(&inputs).map_err(|err| format!("{}: {}", inputs.join(", "), err))?;
+    let expected = SnapshotHeader::new(version, &options.filter.describe());
+    let (graph, names, rebuilt) =
+        load_or_build(&options.snapshot, &expected, || build_from_dataset(options)).map_err(|e| e.to_string())?;
+    if rebuilt {
+        println!("Built the graph and saved it to {}.", options.snapshot);
+    }
+    Ok((graph, names))
+}
+
+// shows an actor's name, falling back to the id
+fn name_of<'a>(actor_id_to_name: &'a ActorNames, id: &'a str) -> &'a str {
+    actor_id_to_name.get(id).map(|n| n.as_str()).unwrap_or(id)
+}
+
+// how many titles an actor is credited on (used to rank actors who share a name)
+fn credit_count(actor_titles: &ActorTitleGraph, id: &str) -> usize {
+    actor_titles.actor_index.get(id).map(|&n| actor_titles.graph.neighbors(n).count()).unwrap_or(0)
+}
+
+// one line per candidate, with enough detail to tell namesakes apart
+fn describe_candidate(actor_titles: &ActorTitleGraph, candidate: &Candidate) -> String {
+    let example_title = actor_titles
+        .actor_index
+        .get(&candidate.actor_id)
+        .and_then(|&n| actor_titles.graph.neighbors(n).map(|t| actor_titles.id(t)).min());
+    format!(
+        "{:<35} {:<11} {:>5} titles{}",
+        candidate.name,
+        candidate.actor_id,
+        credit_count(actor_titles, &candidate.actor_id),
+        example_title.map(|t| format!(", e.g. {}", t)).unwrap_or_default()
+    )
+}
 
-    // build the graph
-    let actor_graph = build_graph(movie_to_actors);
+// turns an --actor/--from/--to argument into an actor id
+// ids are used as they are; anything else is looked up by name, and must have one best match
+fn resolve_actor(
+    actor_titles: &ActorTitleGraph,
+    names: &NameIndex,
+    arg: &str,
+) -> Result<String, String> {
+    if actor_titles.actor_index.contains_key(arg) {
+        return Ok(arg.to_string());
+    }
+    let candidates = names.search(arg, 10, |id| credit_count(actor_titles, id));
+    let best = match candidates.first() {
+        Some(best) => best,
+        None => return Err(format!("no actor matches `{}`", arg)),
+    };
+    let tied: Vec<&Candidate> = candidates.iter().filter(|c| c.rank() == best.rank()).collect();
+    if tied.len() > 1 {
+        let lines: Vec<String> = tied.iter().map(|c| describe_candidate(actor_titles, c)).collect();
+        return Err(format!(
+            "`{}` matches several actors, pass one of their ids instead:\n{}",
+            arg,
+            lines.join("\n")
+        ));
+    }
+    if best.rank() != (0, 0) {
+        println!("Using {} ({}) for `{}`.", best.name, best.actor_id, arg);
+    }
+    Ok(best.actor_id.clone())
+}
 
-    // create actor id to NodeIndex map
-    let actor_id_map: HashMap<String, NodeIndex> = actor_graph
-        .node_references()
-        .map(|(idx, name)| (name.clone(), idx))
-        .collect();
+// prints every actor matching a name
+fn run_find(actor_titles: &ActorTitleGraph, names: &NameIndex, query: &str, top: usize) {
+    let candidates = names.search(query, top, |id| credit_count(actor_titles, id));
+    if candidates.is_empty() {
+        println!("No actor matches `{}`.", query);
+    }
+    for candidate in &candidates {
+        println!("{}", describe_candidate(actor_titles, candidate));
+    }
+}
 
-    // target actor / center node id
-    let priyanka_id = "nm1231899";
+// prints the top rows of a centrality ranking
+fn print_ranking(
+    title: &str,
+    graph: &UnGraph<String, CoStar>,
+    scores: HashMap<NodeIndex, f64>,
+    actor_id_to_name: &ActorNames,
+    top: usize,
+) {
+    println!("\nTop {} {}:", top, title);
+    let mut ranked = scores.into_iter().collect::<Vec<_>>();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    for (node, score) in ranked.into_iter().take(top) {
+        println!("{:<35}: {:.3}", name_of(actor_id_to_name, &graph[node]), score);
+    }
+}
 
-    // extract subgraph with depth 3
-    let subgraph = extract_subgraph_around_actor(&actor_graph, &actor_id_map, priyanka_id, 1);
+// prints the top approximate betweenness scores with their confidence intervals
+fn print_estimates(
+    graph: &UnGraph<String, CoStar>,
+    budget: PivotBudget,
+    rng: &mut StdRng,
+    actor_id_to_name: &ActorNames,
+    top: usize,
+) {
+    println!(
+        "\nTop {} Approximate Betweenness Centrality ({} of {} sources, 95% interval):",
+        top,
+        budget.sources(graph.node_count()),
+        graph.node_count()
+    );
+    let mut ranked = approximate_betweenness(graph, budget, rng).into_iter().collect::<Vec<_>>();
+    ranked.sort_by(|a, b| b.1.score.total_cmp(&a.1.score).then(a.0.cmp(&b.0)));
+    for (node, estimate) in ranked.into_iter().take(top) {
+        println!(
+            "{:<35}: {:.5} [{:.5}, {:.5}]",
+            name_of(actor_id_to_name, &graph[node]),
+            estimate.score,
+            estimate.lower,
+            estimate.upper
+        );
+    }
+}
 
+// the rng for anything random, seeded when --seed is given
+fn make_rng(options: &Options) -> StdRng {
+    match options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_rng(thread_rng()).unwrap(),
+    }
+}
+
+// projects the co-star graph of the ego network, the sample or the whole graph
+// casts are handled as --max-cast, --top-billed and --billing-weights say
+fn project_target(
+    actor_titles: &ActorTitleGraph,
+    target: &Target,
+    options: &Options,
+    rng: &mut StdRng,
+) -> Result<UnGraph<String, CoStar>, String> {
+    let casts = &options.casts;
+    let subgraph = match target {
+        Target::Ego { actor, depth } => {
+            if !actor_titles.actor_index.contains_key(actor) {
+                return Err(format!("unknown actor {}", actor));
+            }
+            actor_titles.project_actors_with(&actor_titles.ego_actors(actor, *depth), casts)
+        }
+        Target::Sample { size } => actor_titles.project_actors_with(&actor_titles.sample_actors(*size, rng), casts),
+        Target::Full => actor_titles.project_with(casts),
+    };
     println!("Subgraph contains {} nodes and {} edges.", subgraph.node_count(), subgraph.edge_count());
+    Ok(subgraph)
+}
+
+// projects the ego network, the sample or the whole graph, then runs each requested centrality measure on it
+fn run_centrality(
+    actor_titles: &ActorTitleGraph,
+    actor_id_to_name: &ActorNames,
+    target: &Target,
+    measures: &[Measure],
+    options: &Options,
+) -> Result<(), String> {
+    let top = options.top;
+    let mut rng = make_rng(options);
+    let subgraph = project_target(actor_titles, target, options, &mut rng)?;
 
-    // run centrality metrics on the subgraph
-    println!("\nTop 10 Degree Centrality:");
-    let mut deg_centrality = degree_centrality(&subgraph).into_iter().collect::<Vec<_>>();
-    deg_centrality.sort_by_key(|&(_, deg)| std::cmp::Reverse(deg));
-    for (node, deg) in deg_centrality.into_iter().take(10) {
-        println!("{:<35}: {:<5}", actor_id_to_name[&subgraph[node]], deg);
+    // for an ego network, also describe how the actor's co-stars are tied to each other
+    if let Target::Ego { actor, .. } = target {
+        if let Some(ego) = subgraph.node_indices().find(|&n| &subgraph[n] == actor) {
+            let metrics = ego_metrics(&subgraph, ego);
+            println!(
+                "{} has {} co-stars with {} ties between them (density {:.3}, effective size {:.1}, {} groups without {}).",
+                name_of(actor_id_to_name, actor),
+                metrics.alters,
+                metrics.alter_ties,
+                metrics.density,
+                metrics.effective_size,
+                metrics.alter_components,
+                name_of(actor_id_to_name, actor),
+            );
+            if let Some(holes) = ego_structural_holes(&subgraph, ego) {
+                println!(
+                    "Weighted by shared titles: effective size {:.1}, efficiency {:.3}, constraint {:.3}, hierarchy {:.3}.",
+                    holes.effective_size, holes.efficiency, holes.constraint, holes.hierarchy
+                );
+            }
+        }
     }
 
-    println!("\nTop 10 Closeness Centrality:");
-    let mut clo_centrality = closeness_centrality(&subgraph).into_iter().collect::<Vec<_>>();
-    clo_centrality.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-    for (node, c) in clo_centrality.into_iter().take(10) {
-        println!("{:<35}: {:.3}", actor_id_to_name[&subgraph[node]], c);
+    for measure in measures {
+        let scores: HashMap<NodeIndex, f64> = match measure {
+            Measure::Degree => degree_centrality(&subgraph).into_iter().map(|(n, d)| (n, d as f64)).collect(),
+            Measure::Strength => strength_centrality(&subgraph),
+            Measure::Closeness => closeness_centrality(&subgraph),
+            Measure::Harmonic => harmonic_centrality(&subgraph),
+            Measure::PageRank => pagerank(&subgraph, &pagerank_options(options)),
+            Measure::Coreness => core_numbers(&subgraph).into_iter().map(|(n, k)| (n, k as f64)).collect(),
+            Measure::Clustering => clustering_coefficients(&subgraph),
+            Measure::Eigenvector => {
+                let (scores, convergence) = eigenvector_centrality(&subgraph, TOLERANCE, MAX_ITERATIONS);
+                report_convergence(*measure, &convergence);
+                scores
+            }
+            Measure::Katz => {
+                // the largest eigenvalue is at most the largest degree, so any alpha below 1 / largest degree
+                // converges
+                let max_degree = subgraph.node_indices().map(|n| subgraph.neighbors(n).count()).max().unwrap_or(0);
+                let limit = 1.0 / max_degree.max(1) as f64;
+                let alpha = options.alpha.unwrap_or(0.9 * limit);
+                if alpha >= limit {
+                    return Err(format!(
+                        "`--alpha` must be below 1 / largest degree ({:.6}) here, got {}",
+                        limit, alpha
+                    ));
+                }
+                let (scores, convergence) = katz_centrality(&subgraph, alpha, TOLERANCE, MAX_ITERATIONS);
+                println!("\nKatz alpha: {}", alpha);
+                report_convergence(*measure, &convergence);
+                scores
+            }
+            Measure::Betweenness => betweenness_centrality(&subgraph),
+            Measure::ApproxBetweenness => {
+                print_estimates(&subgraph, options.pivots, &mut rng, actor_id_to_name, top);
+                continue;
+            }
+        };
+        print_ranking(&format!("{} Centrality", measure), &subgraph, scores, actor_id_to_name, top);
     }
+    Ok(())
+}
 
-    println!("\nTop 10 Betweenness Centrality:");
-    let mut bet_centrality = betweenness_centrality(&subgraph).into_iter().collect::<Vec<_>>();
-    bet_centrality.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-    for (node, b) in bet_centrality.into_iter().take(10) {
-        println!("{:<35}: {:.3}", actor_id_to_name[&subgraph[node]], b);
+// says how many iterations a power-iteration measure took, and warns when it never settled
+fn report_convergence(measure: Measure, convergence: &Convergence) {
+    if convergence.converged {
+        println!("\n{} converged after {} iterations.", measure, convergence.iterations);
+    } else {
+        println!(
+            "\nWarning: {} did not converge after {} iterations (last change {:.3e}); the ranking may be unreliable.",
+            measure, convergence.iterations, convergence.change
+        );
     }
+}
 
+fn pagerank_options(options: &Options) -> PageRankOptions {
+    PageRankOptions { damping: options.damping, ..PageRankOptions::default() }
+}
 
+// ranks the actors near one or more seed actors by personalized pagerank from the seeds
+// only actors within `depth` hops of a seed are projected, since the rest would score (almost) nothing
+fn run_related(
+    actor_titles: &ActorTitleGraph,
+    actor_id_to_name: &ActorNames,
+    seeds: &[String],
+    depth: usize,
+    options: &Options,
+) {
+    let mut actors: Vec<NodeIndex> = seeds.iter().flat_map(|seed| actor_titles.ego_actors(seed, depth)).collect();
+    actors.sort_unstable();
+    actors.dedup();
+    let subgraph = actor_titles.project_actors_with(&actors, &options.casts);
+    println!("Subgraph contains {} nodes and {} edges.", subgraph.node_count(), subgraph.edge_count());
 
-    //random sample graph of 500 nodes
-    let mut rng = thread_rng(); 
-    let sampled_graph = random_actor_subgraph(&actor_graph, 500, &mut rng); 
+    let seed_nodes: Vec<NodeIndex> = subgraph.node_indices().filter(|&n| seeds.contains(&subgraph[n])).collect();
+    let mut scores = personalized_pagerank(&subgraph, &seed_nodes, &pagerank_options(options));
+    for seed in &seed_nodes {
+        scores.remove(seed);
+    }
+    let seed_names: Vec<&str> = seeds.iter().map(|s| name_of(actor_id_to_name, s)).collect();
+    let title = format!("Actors Related to {} (personalized PageRank)", seed_names.join(", "));
+    print_ranking(&title, &subgraph, scores, actor_id_to_name, options.top);
+}
 
-    println!("Sampled subgraph has {} nodes and {} edges.", sampled_graph.node_count(), sampled_graph.edge_count());
+// splits the ego network, the sample or the whole graph into communities and prints the largest ones,
+// each with its best-connected members
+fn run_communities(
+    actor_titles: &ActorTitleGraph,
+    actor_id_to_name: &ActorNames,
+    target: &Target,
+    method: CommunityMethod,
+    options: &Options,
+) -> Result<(), String> {
+    let mut rng = make_rng(options);
+    let subgraph = project_target(actor_titles, target, options, &mut rng)?;
+    let mut members = match method {
+        CommunityMethod::Louvain { resolution } => {
+            let communities = louvain_communities(&subgraph, resolution);
+            println!(
+                "{} communities at resolution {} (modularity {:.3}).",
+                communities.count, resolution, communities.modularity
+            );
+            communities.members()
+        }
+        CommunityMethod::LabelPropagation => {
+            let members: Vec<Vec<NodeIndex>> = label_propagation(&subgraph, &mut rng).into_values().collect();
+            let community: HashMap<NodeIndex, usize> =
+                members.iter().enumerate().flat_map(|(c, m)| m.iter().map(move |&n| (n, c))).collect();
+            println!(
+                "{} communities by label propagation (modularity {:.3}).",
+                members.len(),
+                modularity(&subgraph, &community, 1.0)
+            );
+            members
+        }
+    };
 
-    // run centrality metrics on the subgraph
-    println!("\nTop 10 Degree Centrality on Sample:");
-    let mut deg_sample = degree_centrality(&sampled_graph).into_iter().collect::<Vec<_>>();
-    deg_sample.sort_by_key(|&(_, deg)| std::cmp::Reverse(deg));
-    for (node, deg) in deg_sample.into_iter().take(10) {
-        println!("{:<35} {}", actor_id_to_name[&sampled_graph[node]], deg);
+    let degree = degree_centrality(&subgraph);
+    members.sort_by_key(|m| (std::cmp::Reverse(m.len()), m.iter().min().copied()));
+    println!("\nTop {} communities by size:", options.top);
+    for mut community in members.into_iter().take(options.top) {
+        community.sort_by_key(|n| std::cmp::Reverse(degree[n]));
+        let names: Vec<&str> = community.iter().take(5).map(|&n| name_of(actor_id_to_name, &subgraph[n])).collect();
+        println!("{:<10} actors, including {}", community.len(), names.join(", "));
     }
+    Ok(())
+}
 
-    println!("\nTop 10 Closeness Centrality on Sample:");
-    let mut clo_sample = closeness_centrality(&sampled_graph).into_iter().collect::<Vec<_>>();
-    clo_sample.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-    for (node, c) in clo_sample.into_iter().take(10) {
-        println!("{:<35} {:.3}", actor_id_to_name[&sampled_graph[node]], c);
+// names up to three actors, and how many more there are
+fn list_names(graph: &UnGraph<String, CoStar>, nodes: &[NodeIndex], actor_id_to_name: &ActorNames) -> String {
+    let mut names: Vec<&str> = nodes.iter().take(3).map(|&n| name_of(actor_id_to_name, &graph[n])).collect();
+    let more = format!("{} more", nodes.len().saturating_sub(3));
+    if nodes.len() > 3 {
+        names.push(&more);
     }
+    names.join(", ")
+}
+
+// prints the actors and the collaborations whose removal would split the graph, with the pieces left behind
+fn run_cuts(
+    actor_titles: &ActorTitleGraph,
+    actor_id_to_name: &ActorNames,
+    target: &Target,
+    options: &Options,
+) -> Result<(), String> {
+    let subgraph = project_target(actor_titles, target, options, &mut make_rng(options))?;
 
-    println!("\nTop 10 Betweenness Centrality on Sample:");
-    let mut bet_sample = betweenness_centrality(&sampled_graph).into_iter().collect::<Vec<_>>();
-    bet_sample.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-    for (node, b) in bet_sample.into_iter().take(10) {
-        println!("{:<35} {:.3}", actor_id_to_name[&sampled_graph[node]], b);
+    let points = articulation_points(&subgraph);
+    println!("\n{} actors are the only link between groups; top {} by actors cut off:", points.len(), options.top);
+    for point in points.iter().take(options.top) {
+        println!(
+            "{:<35}: cuts off {} actors into {} pieces",
+            name_of(actor_id_to_name, &subgraph[point.node]),
+            point.split_off(),
+            point.pieces.len()
+        );
+        for piece in &point.pieces {
+            println!("    {:<8} actors, via {}", piece.size, list_names(&subgraph, &piece.neighbours, actor_id_to_name));
+        }
     }
 
+    let found = bridges(&subgraph);
+    println!("\n{} collaborations are the only link between groups; top {} by smaller side:", found.len(), options.top);
+    for bridge in found.iter().take(options.top) {
+        let (a, b) = bridge.ends;
+        println!(
+            "{} ({} actors) -- {} ({} actors), in {}",
+            name_of(actor_id_to_name, &subgraph[a]),
+            bridge.sizes.0,
+            name_of(actor_id_to_name, &subgraph[b]),
+            bridge.sizes.1,
+            subgraph[bridge.edge].titles.join(", ")
+        );
+    }
+    Ok(())
+}
 
-    // find shortest path between major Bollywood actor and major Hollywood actor
-    let amitabh_id = "nm0000821"; // amitabh bachchan id
-    let timothee_id = "nm3154303"; // timothee chalamet id
+// ranks the actors of the ego network, the sample or the whole graph as brokers: by effective size, then by
+// lowest constraint
+fn run_brokers(
+    actor_titles: &ActorTitleGraph,
+    actor_id_to_name: &ActorNames,
+    target: &Target,
+    options: &Options,
+) -> Result<(), String> {
+    let subgraph = project_target(actor_titles, target, options, &mut make_rng(options))?;
+    let mut ranked: Vec<_> = structural_holes(&subgraph).into_iter().collect();
+    ranked.sort_by(|(a, ha), (b, hb)| {
+        hb.effective_size
+            .total_cmp(&ha.effective_size)
+            .then(ha.constraint.total_cmp(&hb.constraint))
+            .then(a.cmp(b))
+    });
+    println!("\nTop {} brokers:", options.top);
+    println!("{:<35}  {:>9} {:>10} {:>10} {:>9}", "", "eff. size", "efficiency", "constraint", "hierarchy");
+    for (node, holes) in ranked.into_iter().take(options.top) {
+        println!(
+            "{:<35}: {:>9.1} {:>10.3} {:>10.3} {:>9.3}",
+            name_of(actor_id_to_name, &subgraph[node]),
+            holes.effective_size,
+            holes.efficiency,
+            holes.constraint,
+            holes.hierarchy
+        );
+    }
+    Ok(())
+}
 
-    println!("\nShortest Path from Amitabh Bachchan to Timothée Chalamet:");
+// prints how many actors are in each k-core, and who is in the innermost one (best connected first)
+fn run_cores(
+    actor_titles: &ActorTitleGraph,
+    actor_id_to_name: &ActorNames,
+    target: &Target,
+    options: &Options,
+) -> Result<(), String> {
+    let subgraph = project_target(actor_titles, target, options, &mut make_rng(options))?;
+    let report = core_report(&core_numbers(&subgraph));
+    println!("\nActors in each k-core:");
+    for (k, size) in report.sizes.iter().enumerate() {
+        println!("{:>5}-core: {}", k, size);
+    }
 
-    let path_len = shortest_path_length(&actor_graph, amitabh_id, timothee_id);
+    let degree = degree_centrality(&subgraph);
+    let mut innermost = report.innermost.clone();
+    innermost.sort_by_key(|n| (std::cmp::Reverse(degree[n]), *n));
+    println!("\nInnermost ({}-core) actors, top {} of {} by degree:", report.max_core, options.top, innermost.len());
+    for node in innermost.into_iter().take(options.top) {
+        println!("{:<35}: {}", name_of(actor_id_to_name, &subgraph[node]), degree[&node]);
+    }
+    Ok(())
+}
 
-    //print length of path (if it exists)
-    match path_len {
-        Some(length) => {
+// prints how tightly knit the ego network, the sample or the whole graph is
+fn run_clustering(
+    actor_titles: &ActorTitleGraph,
+    actor_id_to_name: &ActorNames,
+    target: &Target,
+    options: &Options,
+) -> Result<(), String> {
+    let subgraph = project_target(actor_titles, target, options, &mut make_rng(options))?;
+    let triangles = triangle_counts(&subgraph);
+    let coefficients = clustering_coefficients(&subgraph);
+    println!(
+        "{} triangles, transitivity {:.3}, average clustering {:.3}.",
+        triangles.values().sum::<usize>() / 3,
+        transitivity(&subgraph, &triangles),
+        average_clustering(&coefficients)
+    );
+    if let Target::Ego { actor, .. } = target {
+        if let Some(ego) = subgraph.node_indices().find(|&n| &subgraph[n] == actor) {
             println!(
-                "Shortest path length from {} to {}: {}",
-                actor_id_to_name[amitabh_id],
-                actor_id_to_name[timothee_id],
-                length
+                "{} is in {} triangles (clustering {:.3}).",
+                name_of(actor_id_to_name, actor),
+                triangles[&ego],
+                coefficients[&ego]
             );
         }
-        None => {
-            println!("No path found between Amitabh Bachchan and Timothée Chalamet.");
+    }
+    Ok(())
+}
+
+// ranks the actors within `depth` hops of an actor by how likely they are to work with them next
+fn run_collaborators(
+    actor_titles: &ActorTitleGraph,
+    actor_id_to_name: &ActorNames,
+    actor: &str,
+    score: LinkScore,
+    depth: usize,
+    options: &Options,
+) -> Result<(), String> {
+    let ranked = likely_collaborators_within(actor_titles, actor, depth, score, options.top, &options.casts)
+        .ok_or_else(|| format!("unknown actor {}", actor))?;
+    println!("\nTop {} likely next collaborators of {} ({}):", options.top, name_of(actor_id_to_name, actor), score);
+    for (id, value) in ranked {
+        println!("{:<35}: {:.3}", name_of(actor_id_to_name, &id), value);
+    }
+    Ok(())
+}
+
+// builds the co-star graph as of the cutoff year straight from the dataset (the snapshot has no years),
+// then reports how well each link prediction score foresaw the collaborations formed after it
+// years come from title.basics when --titles is given, otherwise from the cleaned tsv's startYear column
+fn run_backtest(backtest: &BacktestOptions, options: &Options) -> Result<(), String> {
+    let (movie_to_actors, _, years, billing, billed) = read_credits(options).map_err(|e| e.to_string())?;
+    check_billing(billed, options)?;
+    if years.is_empty() {
+        return Err("backtest needs title years: pass --titles title.basics.tsv.gz".to_string());
+    }
+    let graph = build_graph_with_casts(movie_to_actors, &years, &billing, &options.casts, |_| true);
+
+    let mut rng = make_rng(options);
+    let split = split_at_year(&graph, backtest, &mut rng);
+    println!(
+        "Known by {}: {} collaborations. Scoring {} collaborations that started later against {} that never happened.",
+        backtest.cutoff,
+        split.train.edge_count(),
+        split.positives.len(),
+        split.negatives.len()
+    );
+    println!("\n{:<25} {:>7} {:>12} {:>18}", "score", "AUC", format!("precision@{}", backtest.k), "average precision");
+    for evaluation in evaluate_scores(&split, backtest.k, &mut rng) {
+        println!(
+            "{:<25} {:>7.3} {:>12.3} {:>18.3}",
+            evaluation.score.to_string(),
+            evaluation.auc,
+            evaluation.precision_at_k,
+            evaluation.average_precision
+        );
+    }
+    Ok(())
+}
+
+// prints the chain of actors, and the titles that link them, between two actors
+fn run_path(actor_titles: &ActorTitleGraph, actor_id_to_name: &ActorNames, from: &str, to: &str) {
+    let (from_name, to_name) = (name_of(actor_id_to_name, from), name_of(actor_id_to_name, to));
+    match actor_titles.shortest_path(from, to) {
+        Some(path) => {
+            println!("Shortest path length from {} to {}: {}", from_name, to_name, path.len() / 2);
+            println!("{}", actor_titles.describe_path(&path, actor_id_to_name));
+        }
+        None => println!("No path found between {} and {}.", from_name, to_name),
+    }
+}
+
+// prints the number of components and the largest ones
+fn run_components(actor_titles: &ActorTitleGraph, actor_id_to_name: &ActorNames, top: usize) {
+    let mut components: Vec<Vec<NodeIndex>> = actor_titles.actor_components().into_values().collect();
+    components.sort_by_key(|c| std::cmp::Reverse(c.len()));
+    println!("{} connected components.", components.len());
+    println!("\nTop {} components by size:", top);
+    for component in components.iter().take(top) {
+        let example = name_of(actor_id_to_name, actor_titles.id(component[0]));
+        println!("{:<10} actors, including {}", component.len(), example);
+    }
+}
+
+// lists the titles with the biggest casts and the co-star edges each would add, without projecting anything,
+// so a --max-cast can be picked before building a co-star graph of the whole dataset
+fn run_casts(actor_titles: &ActorTitleGraph, options: &Options) {
+    let limit = options.casts.limit.as_ref();
+    let report = cast_report(actor_titles.cast_sizes(), limit, options.top);
+    println!("Every cast together adds up to {} co-star edges (repeat collaborations share one).", report.total_edges);
+    if let Some(limit) = limit {
+        let share = report.edges_over_limit as f64 / report.total_edges.max(1) as f64;
+        println!(
+            "{} titles have more than {} actors; they add {} of those edges ({:.1}%).",
+            report.over_limit,
+            limit.max_cast,
+            report.edges_over_limit,
+            100.0 * share
+        );
+    }
+    println!("\nTop {} titles by cast size:", options.top);
+    println!("{:<12} {:>8} {:>12}", "title", "actors", "edges");
+    for (title, cast, edges) in &report.largest {
+        println!("{:<12} {:>8} {:>12}", title, cast, edges);
+    }
+}
+
+// prints the overall shape of the graph
+// with --titles, also breaks the titles down by type and decade
+fn run_stats(actor_titles: &ActorTitleGraph, options: &Options) -> Result<(), String> {
+    let components = actor_titles.actor_components();
+    let largest_component = components.values().map(|c| c.len()).max().unwrap_or(0);
+    println!(
+        "Full graph has {} actors, {} titles, {} credits and {} connected components (largest has {} actors).",
+        actor_titles.actor_count(),
+        actor_titles.title_count(),
+        actor_titles.graph.edge_count(),
+        components.len(),
+        largest_component
+    );
+
+    if let Some(path) = &options.titles {
+        let titles = read_titles(path, |t| actor_titles.title_index.contains_key(t)).map_err(|e| e.to_string())?;
+        println!("{} of the titles are in {}.", titles.len(), path);
+        let mut by_type: HashMap<&str, usize> = HashMap::new();
+        let mut by_decade: HashMap<u16, usize> = HashMap::new();
+        for info in titles.values() {
+            *by_type.entry(info.title_type.as_str()).or_default() += 1;
+            if let Some(year) = info.start_year {
+                *by_decade.entry(year / 10 * 10).or_default() += 1;
+            }
+        }
+        let mut by_type: Vec<_> = by_type.into_iter().collect();
+        by_type.sort_by_key(|&(t, n)| (std::cmp::Reverse(n), t));
+        println!("\nTitles by type:");
+        for (title_type, count) in by_type {
+            println!("{:<15} {}", title_type, count);
+        }
+        let mut by_decade: Vec<_> = by_decade.into_iter().collect();
+        by_decade.sort_unstable();
+        println!("\nTitles by decade:");
+        for (decade, count) in by_decade {
+            println!("{}s {:>10}", decade, count);
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, options) = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+    if command == Command::Help {
+        println!("{}", USAGE);
+        return;
+    }
+    // backtesting reads the dataset itself, since it needs the title years
+    if let Command::Backtest { options: backtest } = &command {
+        if let Err(err) = run_backtest(backtest, &options) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let (actor_titles, actor_id_to_name) = match load_graph(&options) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("Could not read the dataset: {}", err);
+            std::process::exit(1);
+        }
+    };
+    if let Err(err) = check_billing(actor_titles.billed, &options) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+
+    // the name index is only built when a command refers to an actor
+    let names = || NameIndex::build(&actor_id_to_name);
+    // an ego network's actor may be given by name too
+    let resolve_target = |target: &Target| match target {
+        Target::Ego { actor, depth } => {
+            resolve_actor(&actor_titles, &names(), actor).map(|actor| Target::Ego { actor, depth: *depth })
+        }
+        other => Ok(other.clone()),
+    };
+    let result = match &command {
+        Command::Path { from, to } => {
+            let names = names();
+            resolve_actor(&actor_titles, &names, from).and_then(|from| {
+                let to = resolve_actor(&actor_titles, &names, to)?;
+                run_path(&actor_titles, &actor_id_to_name, &from, &to);
+                Ok(())
+            })
+        }
+        Command::Centrality { target, measures } => resolve_target(target).and_then(|target| {
+            run_centrality(&actor_titles, &actor_id_to_name, &target, measures, &options)
+        }),
+        Command::Communities { target, method } => resolve_target(target).and_then(|target| {
+            run_communities(&actor_titles, &actor_id_to_name, &target, *method, &options)
+        }),
+        Command::Related { actors, depth } => {
+            let names = names();
+            actors
+                .iter()
+                .map(|actor| resolve_actor(&actor_titles, &names, actor))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|seeds| run_related(&actor_titles, &actor_id_to_name, &seeds, *depth, &options))
+        }
+        Command::Cuts { target } => resolve_target(target).and_then(|target| {
+            run_cuts(&actor_titles, &actor_id_to_name, &target, &options)
+        }),
+        Command::Brokers { target } => resolve_target(target).and_then(|target| {
+            run_brokers(&actor_titles, &actor_id_to_name, &target, &options)
+        }),
+        Command::Cores { target } => resolve_target(target).and_then(|target| {
+            run_cores(&actor_titles, &actor_id_to_name, &target, &options)
+        }),
+        Command::Clustering { target } => resolve_target(target).and_then(|target| {
+            run_clustering(&actor_titles, &actor_id_to_name, &target, &options)
+        }),
+        Command::Collaborators { actor, score, depth } => {
+            resolve_actor(&actor_titles, &names(), actor).and_then(|actor| {
+                run_collaborators(&actor_titles, &actor_id_to_name, &actor, *score, *depth, &options)
+            })
+        }
+        Command::Find { name } => {
+            run_find(&actor_titles, &names(), name, options.top);
+            Ok(())
+        }
+        Command::Components => {
+            run_components(&actor_titles, &actor_id_to_name, options.top);
+            Ok(())
+        }
+        Command::Stats => run_stats(&actor_titles, &options),
+        Command::Casts => {
+            run_casts(&actor_titles, &options);
+            Ok(())
         }
+        Command::Help | Command::Backtest { .. } => Ok(()),
+    };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        std::process::exit(1);
     }
 }
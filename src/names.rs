@@ -0,0 +1,246 @@
+//looks up actors by name instead of by nm id
+//names are compared after folding case and accents ("Timothee" finds "Timothée") and splitting into words,
+//and each word may have a couple of typos, so "priyanka chopra" and "Priyanka Chopre Jonas" both find
+//Priyanka Chopra Jonas
+
+use std::collections::{HashMap, HashSet};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::parser::ActorNames;
+
+//lowercases a name, strips accents and punctuation, and splits it into words
+pub fn normalize(name: &str) -> Vec<String> {
+    let folded: String = name
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(|c| c.to_lowercase())
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    folded.split_whitespace().map(|w| w.to_string()).collect()
+}
+
+//the edit distance (insertions, deletions, substitutions) between two words, or None if it is above max
+fn bounded_levenshtein(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        curr[0] = i;
+        let mut row_min = curr[0];
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            curr[j] = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost);
+            row_min = row_min.min(curr[j]);
+        }
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    let dist = prev[b.len()];
+    (dist <= max).then_some(dist)
+}
+
+//how many typos a query word may have: none for short words, more for longer ones
+fn allowed_typos(word: &str) -> usize {
+    match word.chars().count() {
+        0..=3 => 0,
+        4..=5 => 1,
+        _ => 2,
+    }
+}
+
+//one search result
+//typos and extra_words are how far the actor's name is from the query (lower is better)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub actor_id: String,
+    pub name: String,
+    pub typos: usize,
+    pub extra_words: usize,
+}
+
+impl Candidate {
+    //candidates with the same rank are equally good matches for the query
+    pub fn rank(&self) -> (usize, usize) {
+        (self.typos, self.extra_words)
+    }
+}
+
+//an index from name words to actors
+pub struct NameIndex {
+    //(actor id, display name, normalized words) for every actor
+    entries: Vec<(String, String, Vec<String>)>,
+    //each normalized word to the entries that contain it
+    words: HashMap<String, Vec<usize>>,
+    //the distinct words grouped by length in chars, so a typo-tolerant lookup only compares the query word
+    //with words whose length is within its allowed typos
+    by_length: Vec<Vec<String>>,
+}
+
+impl NameIndex {
+    //input: the actor id to name map from the parser
+    pub fn build(actor_id_to_name: &ActorNames) -> Self {
+        //sort so entry order (and so tie order in results) doesn't depend on hashmap order
+        let mut sorted: Vec<_> = actor_id_to_name.iter().collect();
+        sorted.sort_unstable();
+
+        let mut entries = Vec::with_capacity(sorted.len());
+        let mut words: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, (id, name)) in sorted.into_iter().enumerate() {
+            let normalized = normalize(name);
+            for word in &normalized {
+                let postings = words.entry(word.clone()).or_default();
+                if postings.last() != Some(&i) {
+                    postings.push(i);
+                }
+            }
+            entries.push((id.clone(), name.clone(), normalized));
+        }
+
+        let mut by_length: Vec<Vec<String>> = Vec::new();
+        for word in words.keys() {
+            let len = word.chars().count();
+            if by_length.len() <= len {
+                by_length.resize(len + 1, Vec::new());
+            }
+            by_length[len].push(word.clone());
+        }
+        NameIndex { entries, words, by_length }
+    }
+
+    //finds the actors whose name best matches the query
+    //input: the query, the maximum number of results, and a popularity score per actor id
+    //(e.g. number of credits) used to order equally good matches, most popular first
+    //output: candidates, best first; every word of the query must match a word of the name
+    pub fn search(&self, query: &str, limit: usize, popularity: impl Fn(&str) -> usize) -> Vec<Candidate> {
+        let query_words = normalize(query);
+        if query_words.is_empty() {
+            return Vec::new();
+        }
+
+        //for each query word, the entries containing a close enough word
+        let mut candidates: Option<HashSet<usize>> = None;
+        for q in &query_words {
+            let max = allowed_typos(q);
+            let mut matches = HashSet::new();
+            if max == 0 {
+                if let Some(postings) = self.words.get(q) {
+                    matches.extend(postings.iter().copied());
+                }
+            } else {
+                //a word more than `max` chars longer or shorter needs more than `max` edits
+                let len = q.chars().count();
+                let nearby = self.by_length.iter().take(len + max + 1).skip(len.saturating_sub(max));
+                for word in nearby.flatten() {
+                    if bounded_levenshtein(q, word, max).is_some() {
+                        matches.extend(self.words[word].iter().copied());
+                    }
+                }
+            }
+            candidates = Some(match candidates {
+                None => matches,
+                Some(prev) => prev.intersection(&matches).copied().collect(),
+            });
+        }
+
+        let mut results: Vec<(Candidate, usize)> = candidates
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|i| {
+                let (id, name, words) = &self.entries[i];
+                //each query word is charged its closest word in the name
+                let typos = query_words
+                    .iter()
+                    .map(|q| words.iter().filter_map(|w| bounded_levenshtein(q, w, allowed_typos(q))).min())
+                    .sum::<Option<usize>>()?;
+                let candidate = Candidate {
+                    actor_id: id.clone(),
+                    name: name.clone(),
+                    typos,
+                    extra_words: words.len().saturating_sub(query_words.len()),
+                };
+                Some((candidate, popularity(id)))
+            })
+            .collect();
+
+        results.sort_by(|(a, pa), (b, pb)| {
+            a.rank().cmp(&b.rank()).then(pb.cmp(pa)).then(a.actor_id.cmp(&b.actor_id))
+        });
+        results.into_iter().take(limit).map(|(c, _)| c).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_index() -> NameIndex {
+        let names: ActorNames = [
+            ("nm1231899", "Priyanka Chopra Jonas"),
+            ("nm3154303", "Timothée Chalamet"),
+            ("nm0000821", "Amitabh Bachchan"),
+            ("nm0000001", "Chris Evans"),
+            ("nm0000002", "Chris Evans"),
+            ("nm0000003", "Christopher Evans Jr."),
+        ]
+        .iter()
+        .map(|(id, name)| (id.to_string(), name.to_string()))
+        .collect();
+        NameIndex::build(&names)
+    }
+
+    //test case and accent folding
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Timothée  CHALAMET"), vec!["timothee", "chalamet"]);
+        assert_eq!(normalize("Christopher Evans Jr."), vec!["christopher", "evans", "jr"]);
+        assert_eq!(bounded_levenshtein("chopra", "chopre", 1), Some(1));
+        assert_eq!(bounded_levenshtein("chopra", "chalamet", 2), None);
+    }
+
+    //test accent-insensitive, typo-tolerant and partial searches
+    #[test]
+    fn test_search() {
+        let index = sample_index();
+        let none = |_: &str| 0;
+
+        let results = index.search("timothee chalamet", 5, none);
+        assert_eq!(results[0].actor_id, "nm3154303");
+        assert_eq!(results[0].rank(), (0, 0));
+
+        let results = index.search("Amitab Bachan", 5, none); //three typos
+        assert_eq!(results[0].actor_id, "nm0000821");
+        assert_eq!(results[0].typos, 3);
+
+        let results = index.search("priyanka chopra", 5, none); //missing a word
+        assert_eq!(results[0].actor_id, "nm1231899");
+        assert_eq!(results[0].extra_words, 1);
+
+        assert!(index.search("Brad Pitt", 5, none).is_empty());
+
+        //words one or two letters longer or shorter than the query word are still compared
+        let results = index.search("Timothe Chalammet", 5, none);
+        assert_eq!(results[0].actor_id, "nm3154303");
+        assert_eq!(results[0].typos, 2);
+        assert!(index.by_length[6].contains(&"chopra".to_string()));
+        assert_eq!(index.by_length.iter().map(|w| w.len()).sum::<usize>(), index.words.len()); //each word once
+    }
+
+    //test that actors sharing a name are all returned, most popular first
+    #[test]
+    fn test_search_same_name() {
+        let index = sample_index();
+        let popularity = |id: &str| if id == "nm0000002" { 50 } else { 1 };
+        let results = index.search("chris evans", 5, popularity);
+        let ids: Vec<_> = results.iter().map(|c| c.actor_id.as_str()).collect();
+        assert_eq!(ids, ["nm0000002", "nm0000001"]); //"christopher" is too far from "chris"
+        assert_eq!(results[0].rank(), results[1].rank());
+    }
+}
@@ -1,9 +1,16 @@
 //parses the tsv such that each movie id is mapped to a list of actor ids and each actor id is mapped to a name
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs::File;
-use csv::ReaderBuilder;
+use std::io::{BufReader, BufWriter, Read, Write};
+use csv::{ByteRecord, ReaderBuilder, StringRecord};
+use flate2::read::MultiGzDecoder;
+use serde::de::{DeserializeOwned, Deserializer};
 use serde::Deserialize;
 
+//IMDb writes missing values as a literal \N
+const IMDB_NULL: &str = "\\N";
+
 #[derive(Debug, Deserialize)]   //a struct representing one row of the file
 pub struct Record {             //used to link an actor to a movie and give the actor's name
     #[serde(rename = "nconst")]
@@ -14,35 +21,636 @@ pub struct Record {             //used to link an actor to a movie and give the
 
     #[serde(rename = "tconst")]
     pub movie_id: String,
+
+    //the title's release year, from an optional startYear column (None when missing or \N)
+    #[serde(rename = "startYear", default, deserialize_with = "imdb_optional")]
+    pub year: Option<u16>,
+
+    //the credit's place in title.principals' billing, from an optional ordering column (lower is billed higher)
+    #[serde(default, deserialize_with = "imdb_optional")]
+    pub ordering: Option<u32>,
+}
+
+//reads a numeric cell, where IMDb's \N (or an empty cell) means unknown
+fn imdb_optional<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: fmt::Display,
+{
+    let cell = String::deserialize(deserializer)?;
+    if cell.is_empty() || cell == IMDB_NULL {
+        return Ok(None);
+    }
+    cell.parse().map(Some).map_err(serde::de::Error::custom)
+}
+
+//what title.basics tells us about one title
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TitleInfo {
+    //movie, tvSeries, tvEpisode, short, ...
+    pub title_type: String,
+    pub start_year: Option<u16>,
+    pub runtime_minutes: Option<u32>,
+    //e.g. ["Action", "Drama"]; empty when IMDb has none
+    pub genres: Vec<String>,
+}
+
+//everything that can go wrong while reading one of the input files
+#[derive(Debug)]
+pub enum ParseError {
+    //the file could not be opened, read or written
+    Io { path: String, source: std::io::Error },
+    //a row could not be turned into a record
+    //line is 1-based and counts the header, field is the column name when we know it
+    Malformed { path: String, line: u64, field: Option<String>, message: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io { path, source } => write!(f, "{}: {}", path, source),
+            ParseError::Malformed { path, line, field: Some(field), message } => {
+                write!(f, "{}:{}: bad value in column `{}`: {}", path, line, field, message)
+            }
+            ParseError::Malformed { path, line, field: None, message } => {
+                write!(f, "{}:{}: {}", path, line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Io { source, .. } => Some(source),
+            ParseError::Malformed { .. } => None,
+        }
+    }
+}
+
+//how read_dataset reacts to a malformed row
+#[derive(Debug, Clone, Default)]
+pub enum ParseMode {
+    //stop at the first bad row and return its error
+    #[default]
+    Strict,
+    //skip bad rows and keep going; if a quarantine path is given, every skipped row is written there
+    //(with its line number and the reason) so the data can be audited later
+    //the file is only created once a row is skipped, so a clean read leaves an earlier quarantine alone
+    Lenient { quarantine_path: Option<String> },
 }
 
+//counts from one pass over a file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseStats {
+    pub rows_read: usize,
+    pub rows_skipped: usize,
+    //rows that said how the credit was billed (from an ordering column)
+    pub rows_billed: usize,
+}
+
+//a map of each movie_id to the actor_ids credited on it, used to construct the graph
+//each cast is in billing order (top billed first) when the input says how credits were billed
+pub type MovieToActors = HashMap<String, Vec<String>>;
+//a map of actor_id to actor_name so its readable
+pub type ActorNames = HashMap<String, String>;
+//a map of movie_id to the year it came out, for the titles whose year is known
+pub type TitleYears = HashMap<String, u16>;
+//a map of movie_id to its title.basics row
+pub type TitleTable = HashMap<String, TitleInfo>;
+//a map of movie_id to the billing (title.principals' ordering) of each credit in its cast, in cast order,
+//with None for a credit that has none; a title missing from it is billed in cast order (1, 2, 3, ...)
+pub type TitleBilling = HashMap<String, Vec<Option<u32>>>;
+
 // Reads the TSV dataset (using parameter path) and returns(as a tuple):
-// 1. a hashmap mapping each movie_id to a list of actor_ids to construct the graph
+// 1. a hashmap mapping each movie_id to a list of actor_ids to construct the graph, in billing order when the
+//    file has an ordering column (otherwise in file order)
 // 2. a hashmap mapping actor_id to actor_name so its readable
-pub fn read_dataset(path: &str) -> (HashMap<String, Vec<String>>, HashMap<String, String>) {
-    let file = File::open(path).expect("Cannot open file");
-    let mut rdr = ReaderBuilder::new()
-        .delimiter(b'\t') //because it is a tsv
-        .has_headers(true)
-        .from_reader(file);
-
-    let mut movie_to_actors: HashMap<String, Vec<String>> = HashMap::new();
-    let mut actor_id_to_name: HashMap<String, String> = HashMap::new();
+// 3. a hashmap mapping movie_id to its year (empty unless the file has a startYear column)
+// 4. a hashmap mapping movie_id to the ordering of each credit in its cast (empty without an ordering column)
+// 5. how many rows were read, how many were skipped and how many had a billing (0 without an ordering column,
+//    in which case the casts are in file order, not billing order)
+// in strict mode the first malformed row aborts the read with a ParseError,
+// in lenient mode malformed rows are skipped and counted instead
+pub fn read_dataset(
+    path: &str,
+    mode: &ParseMode,
+) -> Result<(MovieToActors, ActorNames, TitleYears, TitleBilling, ParseStats), ParseError> {
+    let mut credits: HashMap<String, Vec<(Option<u32>, String)>> = HashMap::new();
+    let mut actor_id_to_name: ActorNames = HashMap::new();
+    let mut title_years: TitleYears = HashMap::new();
+    let mut rows_billed = 0;
 
-    for result in rdr.deserialize() {
-        let record: Record = result.expect("Failed to deserialize record");
+    let mut stats = for_each_row(path, &["nconst", "primaryName", "tconst"], mode, |record: Record| {
+        if let Some(year) = record.year {
+            title_years.entry(record.movie_id.clone()).or_insert(year);
+        }
 
-        // map movie ID to list of actor IDs
-        movie_to_actors
+        // map movie ID to list of actor IDs (with their billing, to sort them by once every row is in)
+        if record.ordering.is_some() {
+            rows_billed += 1;
+        }
+        credits
             .entry(record.movie_id.clone())
             .or_default()
-            .push(record.actor_id.clone());
+            .push((record.ordering, record.actor_id.clone()));
 
         // map actor ID to name
         actor_id_to_name
             .entry(record.actor_id)
             .or_insert(record.actor_name);
+    })?;
+    stats.rows_billed = rows_billed;
+
+    let (movie_to_actors, mut billing) = in_billing_order(credits);
+    if rows_billed == 0 {
+        billing.clear(); //no ordering column, so the casts are only in file order
+    }
+    Ok((movie_to_actors, actor_id_to_name, title_years, billing, stats))
+}
+
+//sorts each title's credits by billing (credits without one go last, in the order they were read) and keeps
+//only the best billed credit of an actor credited more than once
+//output: the casts, and the ordering of each credit in them
+fn in_billing_order(credits: HashMap<String, Vec<(Option<u32>, String)>>) -> (MovieToActors, TitleBilling) {
+    let mut movie_to_actors: MovieToActors = HashMap::new();
+    let mut billing: TitleBilling = HashMap::new();
+    for (movie_id, mut cast) in credits {
+        cast.sort_by_key(|&(ordering, _)| (ordering.is_none(), ordering));
+        let mut seen = HashSet::new();
+        cast.retain(|(_, actor_id)| seen.insert(actor_id.clone()));
+        let (orderings, actors) = cast.into_iter().unzip();
+        billing.insert(movie_id.clone(), orderings);
+        movie_to_actors.insert(movie_id, actors);
+    }
+    (movie_to_actors, billing)
+}
+
+
+#[derive(Debug, Deserialize)]   //one row of title.principals.tsv.gz (only the columns we need)
+struct PrincipalRow {
+    tconst: String,
+    #[serde(deserialize_with = "imdb_optional")]
+    ordering: Option<u32>,
+    nconst: String,
+    category: String,
+}
+
+#[derive(Debug, Deserialize)]   //one row of title.basics.tsv.gz (only the columns we need)
+struct TitleRow {
+    tconst: String,
+    #[serde(rename = "titleType")]
+    title_type: String,
+    #[serde(rename = "startYear", deserialize_with = "imdb_optional")]
+    start_year: Option<u16>,
+    #[serde(rename = "runtimeMinutes", deserialize_with = "imdb_optional")]
+    runtime_minutes: Option<u32>,
+    genres: String,
+}
+
+#[derive(Debug, Deserialize)]   //one row of name.basics.tsv.gz (only the columns we need)
+struct NameRow {
+    nconst: String,
+    #[serde(rename = "primaryName")]
+    primary_name: String,
+}
+
+//opens a tsv reader, decompressing the file on the fly if the path ends in .gz
+//IMDb does not quote its fields, so quoting is turned off (titles and names can contain ")
+fn open_tsv(path: &str) -> Result<csv::Reader<Box<dyn Read>>, ParseError> {
+    let file = File::open(path).map_err(|source| ParseError::Io { path: path.to_string(), source })?;
+    let reader: Box<dyn Read> = if path.ends_with(".gz") {
+        Box::new(MultiGzDecoder::new(BufReader::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+    Ok(ReaderBuilder::new()
+        .delimiter(b'\t') //because it is a tsv
+        .has_headers(true)
+        .quoting(false)
+        .from_reader(reader))
+}
+
+//turns a csv error into a ParseError, naming the column when csv tells us which one it was
+fn csv_error(path: &str, headers: &StringRecord, err: csv::Error) -> ParseError {
+    let line = err.position().map(|p| p.line()).unwrap_or(0);
+    let field = match err.kind() {
+        csv::ErrorKind::Deserialize { err: de, .. } => de
+            .field()
+            .and_then(|i| headers.get(i as usize))
+            .map(|name| name.to_string()),
+        _ => None,
+    };
+    match err.into_kind() {
+        csv::ErrorKind::Io(source) => ParseError::Io { path: path.to_string(), source },
+        csv::ErrorKind::Deserialize { err: de, .. } => {
+            ParseError::Malformed { path: path.to_string(), line, field, message: de.kind().to_string() }
+        }
+        kind => ParseError::Malformed { path: path.to_string(), line, field, message: format!("{:?}", kind) },
+    }
+}
+
+//reads every row of a tsv, deserializes it and hands it to `handle`
+//the header must have every `required` column, and an empty cell in one of them counts as malformed
+//in lenient mode bad rows are counted (and optionally written to the quarantine file) instead of returned
+fn for_each_row<T, F>(
+    path: &str,
+    required: &[&str],
+    mode: &ParseMode,
+    mut handle: F,
+) -> Result<ParseStats, ParseError>
+where
+    T: DeserializeOwned,
+    F: FnMut(T),
+{
+    let mut rdr = open_tsv(path)?;
+    let headers = rdr.headers().map_err(|e| csv_error(path, &StringRecord::new(), e))?.clone();
+    let byte_headers = headers.as_byte_record().clone();
+    //without this check every row would fail to deserialize, and lenient mode would quietly skip them all
+    if let Some(missing) = required.iter().find(|&&column| !headers.iter().any(|h| h == column)) {
+        return Err(ParseError::Malformed {
+            path: path.to_string(),
+            line: 1,
+            field: Some(missing.to_string()),
+            message: "missing column".to_string(),
+        });
+    }
+    let required: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .filter(|(_, h)| required.contains(h))
+        .map(|(i, _)| i)
+        .collect();
+
+    //opened on the first skipped row
+    let mut quarantine: Option<BufWriter<File>> = None;
+
+    let mut stats = ParseStats::default();
+    let mut raw = ByteRecord::new();
+    loop {
+        let parsed = match rdr.read_byte_record(&mut raw) {
+            Ok(false) => break,
+            Ok(true) => parse_row::<T>(path, &headers, &byte_headers, &required, &raw),
+            Err(err) => Err(csv_error(path, &headers, err)),
+        };
+        stats.rows_read += 1;
+
+        match parsed {
+            Ok(row) => handle(row),
+            Err(err @ ParseError::Io { .. }) => return Err(err),
+            Err(err) => match mode {
+                ParseMode::Strict => return Err(err),
+                ParseMode::Lenient { quarantine_path } => {
+                    stats.rows_skipped += 1;
+                    if let Some(q_path) = quarantine_path {
+                        let io_error = |source| ParseError::Io { path: q_path.clone(), source };
+                        let out = match quarantine.as_mut() {
+                            Some(out) => out,
+                            None => quarantine.insert(BufWriter::new(File::create(q_path).map_err(io_error)?)),
+                        };
+                        write_quarantined(out, &raw, &err).map_err(io_error)?;
+                    }
+                }
+            },
+        }
+    }
+
+    if let (Some(mut out), ParseMode::Lenient { quarantine_path: Some(q_path) }) = (quarantine, mode) {
+        out.flush().map_err(|source| ParseError::Io { path: q_path.clone(), source })?;
+    }
+    Ok(stats)
+}
+
+//deserializes one raw row, checking the required cells for emptiness first
+fn parse_row<T: DeserializeOwned>(
+    path: &str,
+    headers: &StringRecord,
+    byte_headers: &ByteRecord,
+    required: &[usize],
+    raw: &ByteRecord,
+) -> Result<T, ParseError> {
+    let line = raw.position().map(|p| p.line()).unwrap_or(0);
+    if let Some(&i) = required.iter().find(|&&i| raw.get(i).is_some_and(|cell| cell.is_empty())) {
+        return Err(ParseError::Malformed {
+            path: path.to_string(),
+            line,
+            field: headers.get(i).map(|h| h.to_string()),
+            message: "empty value".to_string(),
+        });
+    }
+    raw.deserialize(Some(byte_headers)).map_err(|err| csv_error(path, headers, err))
+}
+
+//writes a skipped row to the quarantine file as: line, reason, then the original cells
+fn write_quarantined(out: &mut impl Write, raw: &ByteRecord, err: &ParseError) -> std::io::Result<()> {
+    let line = match err {
+        ParseError::Malformed { line, .. } => *line,
+        ParseError::Io { .. } => 0,
+    };
+    write!(out, "{}\t{}", line, err.to_string().replace('\t', " "))?;
+    for cell in raw.iter() {
+        out.write_all(b"\t")?;
+        out.write_all(cell)?;
+    }
+    out.write_all(b"\n")
+}
+
+//reads the official IMDb dumps directly, so no separate cleaning step is needed
+//input: path to title.principals.tsv.gz and path to name.basics.tsv.gz
+//output: the same (movie_to_actors, actor_id_to_name, billing) as read_dataset, with casts in billing order,
+//and how many rows of the two files were read and skipped as malformed
+//only principals whose category is actor/actress are kept, and rows with \N (or nothing) in a needed field
+//are dropped, as are actors that have no name in name.basics
+pub fn read_imdb_dumps(
+    principals_path: &str,
+    names_path: &str,
+) -> Result<(MovieToActors, ActorNames, TitleBilling, ParseStats), ParseError> {
+    //first pass: collect every (title, actor) credit
+    let mut credits: Vec<(String, Option<u32>, String)> = Vec::new();
+    let mut wanted: HashSet<String> = HashSet::new();
+    let skip_bad_rows = ParseMode::Lenient { quarantine_path: None };
+    let columns = ["tconst", "nconst", "category"];
+    let principals = for_each_row(principals_path, &columns, &skip_bad_rows, |row: PrincipalRow| {
+        if row.category != "actor" && row.category != "actress" {
+            return;
+        }
+        if row.tconst == IMDB_NULL || row.nconst == IMDB_NULL {
+            return;
+        }
+        wanted.insert(row.nconst.clone());
+        credits.push((row.tconst, row.ordering, row.nconst));
+    })?;
+
+    //second pass: look up names, but only for actors that actually appeared in a credit
+    let mut actor_id_to_name: ActorNames = HashMap::new();
+    let names = for_each_row(names_path, &["nconst", "primaryName"], &skip_bad_rows, |row: NameRow| {
+        if row.primary_name == IMDB_NULL || !wanted.contains(&row.nconst) {
+            return;
+        }
+        actor_id_to_name.insert(row.nconst, row.primary_name);
+    })?;
+
+    //join: drop credits whose actor has no name (an actor can be credited twice on one title; the best
+    //billed credit is kept)
+    let mut by_title: HashMap<String, Vec<(Option<u32>, String)>> = HashMap::new();
+    for (movie_id, ordering, actor_id) in credits {
+        if actor_id_to_name.contains_key(&actor_id) {
+            by_title.entry(movie_id).or_default().push((ordering, actor_id));
+        }
     }
 
-    (movie_to_actors, actor_id_to_name)
+    let stats = ParseStats {
+        rows_read: principals.rows_read + names.rows_read,
+        rows_skipped: principals.rows_skipped + names.rows_skipped,
+        rows_billed: 0,
+    };
+    let (movie_to_actors, billing) = in_billing_order(by_title);
+    Ok((movie_to_actors, actor_id_to_name, billing, stats))
+}
+
+//reads IMDb's title.basics dump for the titles we have credits for, to join onto them
+//input: path to title.basics.tsv.gz and which title ids to keep (e.g. those in movie_to_actors, to save memory)
+//output: the title table (titles missing from title.basics are simply absent) and how many rows were read and
+//skipped; malformed rows are skipped, but a file without the columns we need is an error
+pub fn read_title_basics(
+    path: &str,
+    keep: impl Fn(&str) -> bool,
+) -> Result<(TitleTable, ParseStats), ParseError> {
+    let mut titles: TitleTable = HashMap::new();
+    let skip_bad_rows = ParseMode::Lenient { quarantine_path: None };
+    let columns = ["tconst", "titleType", "startYear", "runtimeMinutes", "genres"];
+    let stats = for_each_row(path, &columns, &skip_bad_rows, |row: TitleRow| {
+        if !keep(&row.tconst) {
+            return;
+        }
+        let genres = if row.genres == IMDB_NULL {
+            Vec::new()
+        } else {
+            row.genres.split(',').filter(|g| !g.is_empty()).map(|g| g.to_string()).collect()
+        };
+        let info = TitleInfo {
+            title_type: row.title_type,
+            start_year: row.start_year,
+            runtime_minutes: row.runtime_minutes,
+            genres,
+        };
+        titles.insert(row.tconst, info);
+    })?;
+    Ok((titles, stats))
+}
+
+//the years from a title table, for build_graph_with_years
+pub fn title_years(titles: &TitleTable) -> TitleYears {
+    titles
+        .iter()
+        .filter_map(|(id, info)| info.start_year.map(|year| (id.clone(), year)))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    //helper function to write a gzipped fixture into the temp directory
+    fn write_gz(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("final_project_{}_{}", std::process::id(), name));
+        let mut enc = GzEncoder::new(File::create(&path).unwrap(), Compression::default());
+        enc.write_all(contents.as_bytes()).unwrap();
+        enc.finish().unwrap();
+        path
+    }
+
+    //helper function to write a plain text fixture into the temp directory
+    fn write_tsv(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("final_project_{}_{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    //test reading the raw dumps: non-actors, \N rows and nameless actors are dropped
+    #[test]
+    fn test_read_imdb_dumps() {
+        let principals = write_gz(
+            "principals.tsv.gz",
+            "tconst\tordering\tnconst\tcategory\tjob\tcharacters\n\
+             tt1\t1\tnm1\tactor\t\\N\t[\"Raj\"]\n\
+             tt1\t2\tnm2\tactress\t\\N\t\\N\n\
+             tt1\t3\tnm3\tdirector\t\\N\t\\N\n\
+             tt2\t1\tnm2\tactress\t\\N\t\\N\n\
+             tt2\t2\tnm4\tactor\t\\N\t\\N\n\
+             tt3\t4\tnm1\tactor\t\\N\t\\N\n\
+             tt3\t2\tnm2\tactress\t\\N\t\\N\n\
+             tt3\t3\tnm1\tactor\t\\N\t\\N\n",
+        );
+        let names = write_gz(
+            "names.tsv.gz",
+            "nconst\tprimaryName\tbirthYear\tdeathYear\tprimaryProfession\tknownForTitles\n\
+             nm1\tAmitabh Bachchan\t1942\t\\N\tactor\ttt1\n\
+             nm2\tPriyanka Chopra Jonas\t1982\t\\N\tactress\ttt1\n\
+             nm3\tSome Director\t\\N\t\\N\tdirector\ttt1\n\
+             nm4\t\\N\t\\N\t\\N\tactor\ttt2\n\
+             nm5\n",
+        );
+
+        let (movie_to_actors, actor_id_to_name, billing, stats) =
+            read_imdb_dumps(principals.to_str().unwrap(), names.to_str().unwrap()).unwrap();
+        std::fs::remove_file(principals).unwrap();
+        std::fs::remove_file(names).unwrap();
+
+        assert_eq!(movie_to_actors["tt1"], vec!["nm1".to_string(), "nm2".to_string()]); //director dropped
+        assert_eq!(movie_to_actors["tt2"], vec!["nm2".to_string()]); //nm4 has no name
+        assert_eq!(movie_to_actors["tt3"], vec!["nm2".to_string(), "nm1".to_string()]); //billing order, nm1 once
+        assert_eq!(billing["tt3"], vec![Some(2), Some(3)]); //IMDb's ordering, not the place in the cast
+        assert_eq!(actor_id_to_name.len(), 2);
+        assert_eq!(actor_id_to_name["nm1"], "Amitabh Bachchan");
+        assert_eq!((stats.rows_read, stats.rows_skipped), (13, 1)); //nm5's row is cut short
+    }
+
+    const BAD_DATASET: &str = "nconst\tprimaryName\ttconst\n\
+                               nm1\tAmitabh Bachchan\ttt1\n\
+                               nm2\t\ttt1\n\
+                               nm3\tToo\tMany\tColumns\n\
+                               nm2\tPriyanka Chopra Jonas\ttt2\n";
+
+    //test that strict mode reports the file, line and column of the first bad row
+    #[test]
+    fn test_read_dataset_strict_error() {
+        let path = write_tsv("strict.tsv", BAD_DATASET);
+        let err = read_dataset(path.to_str().unwrap(), &ParseMode::Strict).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        match err {
+            ParseError::Malformed { path: p, line, field, .. } => {
+                assert_eq!(p, path.to_str().unwrap());
+                assert_eq!(line, 3); //header is line 1
+                assert_eq!(field.as_deref(), Some("primaryName"));
+            }
+            other => panic!("expected a malformed row, got {:?}", other),
+        }
+        assert!(matches!(read_dataset("does_not_exist.tsv", &ParseMode::Strict), Err(ParseError::Io { .. })));
+    }
+
+    //test that lenient mode skips, counts and quarantines bad rows
+    #[test]
+    fn test_read_dataset_lenient_quarantine() {
+        let path = write_tsv("lenient.tsv", BAD_DATASET);
+        let quarantine = path.with_extension("quarantine.tsv");
+        let mode = ParseMode::Lenient { quarantine_path: Some(quarantine.to_str().unwrap().to_string()) };
+        let (movie_to_actors, actor_id_to_name, title_years, billing, stats) =
+            read_dataset(path.to_str().unwrap(), &mode).unwrap();
+        let quarantined = std::fs::read_to_string(&quarantine).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&quarantine).unwrap();
+
+        assert_eq!(stats, ParseStats { rows_read: 4, rows_skipped: 2, rows_billed: 0 });
+        assert_eq!(movie_to_actors.len(), 2);
+        assert_eq!(actor_id_to_name["nm2"], "Priyanka Chopra Jonas");
+        assert!(title_years.is_empty()); //no startYear column
+        assert!(billing.is_empty()); //no ordering column
+        let lines: Vec<&str> = quarantined.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("3\t"));
+        assert!(lines[1].starts_with("4\t"));
+
+        //a file without bad rows does not create (or wipe) the quarantine file
+        let clean = write_tsv("clean.tsv", "nconst\tprimaryName\ttconst\nnm1\tAmitabh Bachchan\ttt1\n");
+        let stats = read_dataset(clean.to_str().unwrap(), &mode).unwrap().4;
+        std::fs::remove_file(&clean).unwrap();
+        assert_eq!(stats.rows_skipped, 0);
+        assert!(!quarantine.exists());
+    }
+
+    //test joining title.basics: unknown titles are left out, \N becomes None or no genres
+    #[test]
+    fn test_read_title_basics() {
+        let basics = write_gz(
+            "basics.tsv.gz",
+            "tconst\ttitleType\tprimaryTitle\toriginalTitle\tisAdult\tstartYear\tendYear\truntimeMinutes\tgenres\n\
+             tt1\tmovie\tSholay\tSholay\t0\t1975\t\\N\t204\tAction,Adventure,Comedy\n\
+             tt2\ttvEpisode\tEpisode #1.1\tEpisode #1.1\t0\t2022\t\\N\t\\N\t\\N\n\
+             tt3\tmovie\tNot Credited\tNot Credited\t0\t1999\t\\N\t90\tDrama\n\
+             tt4\tshort\tBroken\tBroken\t0\tsoon\t\\N\t5\tShort\n",
+        );
+        let movie_to_actors: MovieToActors = ["tt1", "tt2", "tt4", "tt5"]
+            .iter()
+            .map(|t| (t.to_string(), vec!["nm1".to_string()]))
+            .collect();
+        let (titles, stats) =
+            read_title_basics(basics.to_str().unwrap(), |t| movie_to_actors.contains_key(t)).unwrap();
+        std::fs::remove_file(basics).unwrap();
+
+        assert_eq!(titles.len(), 2); //tt3 has no credits, tt4 is malformed, tt5 is not in title.basics
+        assert_eq!((stats.rows_read, stats.rows_skipped), (4, 1));
+        assert_eq!(
+            titles["tt1"],
+            TitleInfo {
+                title_type: "movie".to_string(),
+                start_year: Some(1975),
+                runtime_minutes: Some(204),
+                genres: vec!["Action".to_string(), "Adventure".to_string(), "Comedy".to_string()],
+            }
+        );
+        assert_eq!(titles["tt2"].runtime_minutes, None);
+        assert!(titles["tt2"].genres.is_empty());
+        assert_eq!(title_years(&titles), TitleYears::from([("tt1".to_string(), 1975), ("tt2".to_string(), 2022)]));
+
+        //a file without the genres column is refused instead of coming back empty
+        let no_genres = write_tsv(
+            "basics_no_genres.tsv",
+            "tconst\ttitleType\tstartYear\truntimeMinutes\n\
+             tt1\tmovie\t1975\t204\n",
+        );
+        let result = read_title_basics(no_genres.to_str().unwrap(), |_| true);
+        std::fs::remove_file(no_genres).unwrap();
+        match result {
+            Err(ParseError::Malformed { line: 1, field, .. }) => assert_eq!(field.as_deref(), Some("genres")),
+            other => panic!("expected a missing column, got {:?}", other),
+        }
+    }
+
+    //test reading title years from an optional startYear column
+    #[test]
+    fn test_read_dataset_years() {
+        let path = write_tsv(
+            "years.tsv",
+            "nconst\tprimaryName\ttconst\tstartYear\n\
+             nm1\tAmitabh Bachchan\ttt1\t1975\n\
+             nm2\tPriyanka Chopra Jonas\ttt1\t1975\n\
+             nm2\tPriyanka Chopra Jonas\ttt2\t\\N\n\
+             nm3\tTimothée Chalamet\ttt3\tsoon\n",
+        );
+        let result = read_dataset(path.to_str().unwrap(), &ParseMode::Strict);
+        let (movie_to_actors, _, title_years, _, stats) =
+            read_dataset(path.to_str().unwrap(), &ParseMode::Lenient { quarantine_path: None }).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(ParseError::Malformed { line: 5, .. }))); //"soon" is not a year
+        assert_eq!(stats.rows_skipped, 1);
+        assert_eq!(movie_to_actors["tt1"].len(), 2);
+        assert_eq!(title_years, HashMap::from([("tt1".to_string(), 1975)])); //tt2's year is unknown
+    }
+
+    //test that casts come back in billing order when the file has an ordering column
+    #[test]
+    fn test_read_dataset_billing() {
+        let path = write_tsv(
+            "billing.tsv",
+            "nconst\tprimaryName\ttconst\tordering\n\
+             nm3\tExtra\ttt1\t\\N\n\
+             nm2\tSupporting\ttt1\t7\n\
+             nm1\tLead\ttt1\t1\n\
+             nm4\tOther Extra\ttt1\t\\N\n",
+        );
+        let (movie_to_actors, _, _, billing, stats) =
+            read_dataset(path.to_str().unwrap(), &ParseMode::Strict).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(movie_to_actors["tt1"], vec!["nm1", "nm2", "nm3", "nm4"]);
+        assert_eq!(billing["tt1"], vec![Some(1), Some(7), None, None]);
+        assert_eq!(stats.rows_billed, 2);
+    }
 }
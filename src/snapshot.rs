@@ -0,0 +1,413 @@
+//saves the built graph to a compact binary file so later runs can skip parsing and building
+//a snapshot holds the bipartite actor-title graph and the actor id to name map, behind a header that records
+//which input files it was built from and with which build options, so a stale snapshot can be detected
+//the id to NodeIndex maps are not written out: nodes are stored in index order, so rebuilding the maps
+//on load gives back exactly the same NodeIndex values
+
+use petgraph::graph::{NodeIndex, UnGraph};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::time::UNIX_EPOCH;
+
+use crate::bipartite::{ActorTitleGraph, Node};
+use crate::parser::ActorNames;
+
+const MAGIC: &[u8; 8] = b"IMDBSNAP";
+//bump this whenever the layout below changes, so old snapshots are rebuilt instead of misread
+pub const FORMAT_VERSION: u32 = 4;
+
+//identifies the exact input files a snapshot was built from (path, size in bytes, modification time)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatasetVersion {
+    pub files: Vec<(String, u64, u64)>,
+}
+
+//reads the size and modification time of each input file
+pub fn dataset_version(paths: &[&str]) -> io::Result<DatasetVersion> {
+    let mut files = Vec::new();
+    for path in paths {
+        let meta = std::fs::metadata(path)?;
+        let modified = meta
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        files.push((path.to_string(), meta.len(), modified));
+    }
+    Ok(DatasetVersion { files })
+}
+
+//everything a snapshot must match to be reused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotHeader {
+    pub format_version: u32,
+    pub dataset: DatasetVersion,
+    //a canonical description of the options the graph was built with (e.g. filters), compared as text
+    pub build_options: String,
+}
+
+impl SnapshotHeader {
+    pub fn new(dataset: DatasetVersion, build_options: &str) -> Self {
+        SnapshotHeader { format_version: FORMAT_VERSION, dataset, build_options: build_options.to_string() }
+    }
+}
+
+fn write_u8(w: &mut impl Write, v: u8) -> io::Result<()> {
+    w.write_all(&[v])
+}
+
+fn write_u32(w: &mut impl Write, v: u32) -> io::Result<()> {
+    w.write_all(&v.to_le_bytes())
+}
+
+fn write_u64(w: &mut impl Write, v: u64) -> io::Result<()> {
+    w.write_all(&v.to_le_bytes())
+}
+
+fn write_str(w: &mut impl Write, s: &str) -> io::Result<()> {
+    write_u32(w, s.len() as u32)?;
+    w.write_all(s.as_bytes())
+}
+
+fn read_u8(r: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0u8; 1];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+//a reader that knows how many bytes are left in the file, so every length and count read from a (possibly
+//corrupt) snapshot can be checked before anything is allocated for it
+struct Remaining<R> {
+    inner: R,
+    left: u64,
+}
+
+impl Remaining<BufReader<File>> {
+    fn open(path: &str) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let left = file.metadata()?.len();
+        Ok(Remaining { inner: BufReader::new(file), left })
+    }
+}
+
+impl<R> Remaining<R> {
+    //fails unless `count` items of at least `size` bytes each still fit in the file
+    fn check(&self, count: usize, size: u64) -> io::Result<()> {
+        if (count as u64).saturating_mul(size) > self.left {
+            return Err(invalid("a length or count runs past the end of the file"));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Remaining<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.left = self.left.saturating_sub(n as u64);
+        Ok(n)
+    }
+}
+
+fn read_str<R: Read>(r: &mut Remaining<R>) -> io::Result<String> {
+    let len = read_u32(r)? as usize;
+    r.check(len, 1)?;
+    let mut buf = vec![0u8; len];
+    r.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|e| invalid(&e.to_string()))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn write_header(w: &mut impl Write, header: &SnapshotHeader) -> io::Result<()> {
+    w.write_all(MAGIC)?;
+    write_u32(w, header.format_version)?;
+    write_u32(w, header.dataset.files.len() as u32)?;
+    for (path, len, modified) in &header.dataset.files {
+        write_str(w, path)?;
+        write_u64(w, *len)?;
+        write_u64(w, *modified)?;
+    }
+    write_str(w, &header.build_options)
+}
+
+fn read_header_from<R: Read>(r: &mut Remaining<R>) -> io::Result<SnapshotHeader> {
+    let mut magic = [0u8; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a graph snapshot"));
+    }
+    let format_version = read_u32(r)?;
+    if format_version != FORMAT_VERSION {
+        //the rest of the file may have a different layout, so stop here
+        return Ok(SnapshotHeader { format_version, dataset: DatasetVersion { files: vec![] }, build_options: String::new() });
+    }
+    let n_files = read_u32(r)?;
+    r.check(n_files as usize, 20)?; //a length, a size and a time
+    let mut files = Vec::new();
+    for _ in 0..n_files {
+        files.push((read_str(r)?, read_u64(r)?, read_u64(r)?));
+    }
+    let build_options = read_str(r)?;
+    Ok(SnapshotHeader { format_version, dataset: DatasetVersion { files }, build_options })
+}
+
+//writes a snapshot
+//input: where to write it, its header, the graph and the actor names
+pub fn save_snapshot(
+    path: &str,
+    header: &SnapshotHeader,
+    graph: &ActorTitleGraph,
+    actor_id_to_name: &ActorNames,
+) -> io::Result<()> {
+    //write to a temporary file first so a crash never leaves a half-written snapshot behind
+    let tmp_path = format!("{}.tmp", path);
+    let mut w = BufWriter::new(File::create(&tmp_path)?);
+    write_header(&mut w, header)?;
+
+    //whether the edges hold real billing
+    write_u8(&mut w, graph.billed as u8)?;
+
+    //nodes in index order: a tag byte (0 = actor, 1 = title) and the IMDb id
+    write_u32(&mut w, graph.graph.node_count() as u32)?;
+    for node in graph.graph.node_indices() {
+        match &graph.graph[node] {
+            Node::Actor(id) => {
+                write_u8(&mut w, 0)?;
+                write_str(&mut w, id)?;
+            }
+            Node::Title(id) => {
+                write_u8(&mut w, 1)?;
+                write_str(&mut w, id)?;
+            }
+        }
+    }
+
+    //edges as pairs of node indices followed by the billing
+    write_u32(&mut w, graph.graph.edge_count() as u32)?;
+    for edge in graph.graph.edge_indices() {
+        let (a, b) = graph.graph.edge_endpoints(edge).unwrap();
+        write_u32(&mut w, a.index() as u32)?;
+        write_u32(&mut w, b.index() as u32)?;
+        write_u32(&mut w, graph.graph[edge])?;
+    }
+
+    //actor names, sorted so the same graph always gives the same bytes
+    let mut names: Vec<_> = actor_id_to_name.iter().collect();
+    names.sort_unstable();
+    write_u32(&mut w, names.len() as u32)?;
+    for (id, name) in names {
+        write_str(&mut w, id)?;
+        write_str(&mut w, name)?;
+    }
+
+    w.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    std::fs::rename(&tmp_path, path)
+}
+
+//reads just the header, which is cheap, to decide whether a snapshot can be reused
+pub fn read_header(path: &str) -> io::Result<SnapshotHeader> {
+    read_header_from(&mut Remaining::open(path)?)
+}
+
+//reads a whole snapshot back
+//output: the header, the graph (with its id to NodeIndex maps rebuilt) and the actor names
+pub fn load_snapshot(path: &str) -> io::Result<(SnapshotHeader, ActorTitleGraph, ActorNames)> {
+    let mut r = Remaining::open(path)?;
+    let header = read_header_from(&mut r)?;
+    if header.format_version != FORMAT_VERSION {
+        return Err(invalid("snapshot was written by a different format version"));
+    }
+
+    let billed = read_u8(&mut r)? != 0;
+    let n_nodes = read_u32(&mut r)? as usize;
+    r.check(n_nodes, 5)?; //a tag and a length
+    let mut graph = UnGraph::<Node, u32>::with_capacity(n_nodes, 0);
+    let mut actor_index = HashMap::new();
+    let mut title_index = HashMap::new();
+    for _ in 0..n_nodes {
+        let tag = read_u8(&mut r)?;
+        let id = read_str(&mut r)?;
+        match tag {
+            0 => {
+                let idx = graph.add_node(Node::Actor(id.clone()));
+                actor_index.insert(id, idx);
+            }
+            1 => {
+                let idx = graph.add_node(Node::Title(id.clone()));
+                title_index.insert(id, idx);
+            }
+            _ => return Err(invalid("unknown node tag")),
+        }
+    }
+
+    let n_edges = read_u32(&mut r)? as usize;
+    r.check(n_edges, 12)?; //two ends and a billing
+    graph.reserve_edges(n_edges);
+    for _ in 0..n_edges {
+        let a = read_u32(&mut r)? as usize;
+        let b = read_u32(&mut r)? as usize;
+        let billing = read_u32(&mut r)?;
+        if a >= n_nodes || b >= n_nodes {
+            return Err(invalid("edge refers to a missing node"));
+        }
+        graph.add_edge(NodeIndex::new(a), NodeIndex::new(b), billing);
+    }
+
+    let n_names = read_u32(&mut r)? as usize;
+    r.check(n_names, 8)?; //two lengths
+    let mut actor_id_to_name = HashMap::with_capacity(n_names);
+    for _ in 0..n_names {
+        let id = read_str(&mut r)?;
+        let name = read_str(&mut r)?;
+        actor_id_to_name.insert(id, name);
+    }
+
+    Ok((header, ActorTitleGraph { graph, actor_index, title_index, billed }, actor_id_to_name))
+}
+
+//loads the snapshot at `path` if its header matches `expected`; otherwise calls `build`, saves the result
+//as the new snapshot and returns it
+//a snapshot that can't be read (missing, corrupt, old format) is treated as stale
+//output: the graph, the actor names, and whether they had to be rebuilt
+pub fn load_or_build<E, F>(
+    path: &str,
+    expected: &SnapshotHeader,
+    build: F,
+) -> Result<(ActorTitleGraph, ActorNames, bool), E>
+where
+    F: FnOnce() -> Result<(ActorTitleGraph, ActorNames), E>,
+{
+    if read_header(path).is_ok_and(|header| &header == expected) {
+        if let Ok((_, graph, names)) = load_snapshot(path) {
+            return Ok((graph, names, false));
+        }
+    }
+
+    let (graph, names) = build()?;
+    //the snapshot is only a cache, so failing to write it shouldn't fail the run
+    if let Err(err) = save_snapshot(path, expected, &graph, &names) {
+        eprintln!("Could not write snapshot {}: {}", path, err);
+    }
+    Ok((graph, names, true))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //helper function to build a small graph and its names
+    fn sample() -> (ActorTitleGraph, ActorNames) {
+        let mut data = HashMap::new();
+        data.insert("tt1".to_string(), vec!["nm1".to_string(), "nm2".to_string()]);
+        data.insert("tt2".to_string(), vec!["nm2".to_string(), "nm3".to_string()]);
+        let names: ActorNames = [("nm1", "Amitabh Bachchan"), ("nm2", "X"), ("nm3", "Timothée Chalamet")]
+            .iter()
+            .map(|(id, name)| (id.to_string(), name.to_string()))
+            .collect();
+        (ActorTitleGraph::build(&data), names)
+    }
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("final_project_{}_{}", std::process::id(), name))
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    fn header(size: u64, options: &str) -> SnapshotHeader {
+        SnapshotHeader::new(DatasetVersion { files: vec![("data.tsv".to_string(), size, 42)] }, options)
+    }
+
+    //test that a saved snapshot loads back with the same nodes, edges, indices and names
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let (graph, names) = sample();
+        let path = temp_path("roundtrip.snap");
+        save_snapshot(&path, &header(100, "all"), &graph, &names).unwrap();
+        let (loaded_header, loaded, loaded_names) = load_snapshot(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded_header, header(100, "all"));
+        assert_eq!(loaded.graph.node_count(), graph.graph.node_count());
+        assert_eq!(loaded.graph.edge_count(), graph.graph.edge_count());
+        assert_eq!(loaded.actor_index, graph.actor_index);
+        assert_eq!(loaded.title_index, graph.title_index);
+        assert_eq!(loaded_names, names);
+        assert_eq!(loaded.shortest_path_length("nm1", "nm3"), Some(2));
+        assert!(loaded.graph.edge_indices().all(|e| loaded.graph[e] == graph.graph[e])); //billing survives
+        assert!(loaded.billed);
+
+        let unbilled = ActorTitleGraph { billed: false, ..graph };
+        save_snapshot(&path, &header(100, "all"), &unbilled, &names).unwrap();
+        let (_, loaded, _) = load_snapshot(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(!loaded.billed);
+    }
+
+    //test that a changed dataset or changed options triggers a rebuild, and a matching header doesn't
+    #[test]
+    fn test_load_or_build_detects_stale() {
+        let path = temp_path("stale.snap");
+        let _ = std::fs::remove_file(&path);
+        let build = || Ok::<_, ()>(sample());
+
+        let (_, _, rebuilt) = load_or_build(&path, &header(100, "all"), build).unwrap();
+        assert!(rebuilt); //no snapshot yet
+        let (graph, _, rebuilt) = load_or_build(&path, &header(100, "all"), build).unwrap();
+        assert!(!rebuilt);
+        assert_eq!(graph.actor_count(), 3);
+        let (_, _, rebuilt) = load_or_build(&path, &header(101, "all"), build).unwrap();
+        assert!(rebuilt); //dataset changed
+        let (_, _, rebuilt) = load_or_build(&path, &header(101, "movies only"), build).unwrap();
+        assert!(rebuilt); //options changed
+
+        std::fs::write(&path, b"garbage").unwrap();
+        let (_, _, rebuilt) = load_or_build(&path, &header(101, "movies only"), build).unwrap();
+        assert!(rebuilt); //corrupt snapshot
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    //test that lengths and counts running past the end of the file are rejected before allocating
+    #[test]
+    fn test_corrupt_lengths() {
+        let path = temp_path("huge.snap");
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(FORMAT_VERSION.to_le_bytes());
+        bytes.extend(1u32.to_le_bytes()); //one input file
+        bytes.extend(u32::MAX.to_le_bytes()); //whose path is 4 GiB long
+        std::fs::write(&path, &bytes).unwrap();
+        let err = read_header(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let (_, _, rebuilt) = load_or_build(&path, &header(100, "all"), || Ok::<_, ()>(sample())).unwrap();
+        assert!(rebuilt);
+
+        //a valid header followed by a huge node count
+        let (graph, names) = sample();
+        save_snapshot(&path, &header(100, "all"), &graph, &names).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        let mut head = Vec::new();
+        write_header(&mut head, &header(100, "all")).unwrap();
+        let nodes_at = head.len() + 1; //after the header and the billed byte
+        bytes[nodes_at..nodes_at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(load_snapshot(&path), Err(err) if err.kind() == io::ErrorKind::InvalidData));
+        std::fs::remove_file(&path).unwrap();
+    }
+}